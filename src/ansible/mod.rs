@@ -1,13 +1,50 @@
 use std::fs;
 
 use crate::api::output::file::Directory;
-use crate::api::output::file::Directory::{AnsibleRole, ProjectRoot};
+use crate::api::output::file::Directory::{AnsibleRole, AnsibleRoles, ProjectRoot};
 use crate::api::task;
 
+const ARCHETYPE_PLAYBOOK_FILENAME: &str = "archetype.yml";
+
+pub struct AnsiblePlaybook {
+    pub name: String,
+    /// Path of the playbook relative to the project dir.
+    pub path: String,
+}
+
+impl AnsiblePlaybook {
+    pub fn archetype() -> Self {
+        Self {
+            name: String::from("archetype"),
+            path: String::from(ARCHETYPE_PLAYBOOK_FILENAME),
+        }
+    }
+
+    pub fn instance(name: &str, path: &str) -> Self {
+        Self {
+            name: String::from(name),
+            path: String::from(path),
+        }
+    }
+
+    pub fn filename(&self) -> String {
+        format!("{}.yml", self.name)
+    }
+
+    /// Copies the playbook and creates the roles dir in the dir that packer is run from.
+    pub fn stage(&self, dir: Directory) -> task::Result<()> {
+        AnsibleRoles.create_dir()?;
+        copy_playbook(&self.path, dir, &self.filename())
+    }
+}
+
 pub fn init_archetype_playbook() -> task::Result<()> {
+    if ProjectRoot.join_path(ARCHETYPE_PLAYBOOK_FILENAME).exists() {
+        return task::SUCCESS;
+    }
     ProjectRoot.write_bytes(
         None,
-        "archetype.yml",
+        ARCHETYPE_PLAYBOOK_FILENAME,
         include_bytes!("archetype.yml").to_vec(),
     )
 }
@@ -20,7 +57,11 @@ pub fn init_roles_dir() -> task::Result<()> {
     )
 }
 
-pub fn copy_archetype_playbook(dir: Directory) -> task::Result<()> {
-    fs::copy("archetype.yml", dir.join_path("archetype.yml"))?;
+pub fn copy_playbook(path: &str, dir: Directory, filename: &str) -> task::Result<()> {
+    let playbook_path = ProjectRoot.join_path(path);
+    if !playbook_path.is_file() {
+        return task::Error::result(format!("{} playbook not found in project dir", path));
+    }
+    fs::copy(playbook_path, dir.join_path(filename))?;
     Ok(())
 }
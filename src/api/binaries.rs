@@ -1,18 +1,24 @@
 use std::collections::HashMap;
+use std::{env, fmt};
 use std::path::PathBuf;
 
 use which::which;
 
 use crate::api::binaries::BinaryName::*;
+use crate::api::config::SwoonConfig;
+use crate::api::task;
+use crate::api::util::ProjectDir;
 
-#[derive(Clone, Eq, Hash, PartialEq)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub enum BinaryName {
     // Ansible,
-    // Aws,
-    // Azure,
+    Aws,
+    Azure,
     // Docker,
+    Doctl,
     Gcloud,
-    // Packer,
+    Git,
+    Packer,
     // Terraform,
 }
 
@@ -20,11 +26,13 @@ impl BinaryName {
     pub fn all() -> Vec<Self> {
         [
             // Ansible,
-            // Aws,
-            // Azure,
+            Aws,
+            Azure,
             // Docker,
+            Doctl,
             Gcloud,
-            // Packer,
+            Git,
+            Packer,
             // Terraform,
         ].to_vec()
     }
@@ -32,69 +40,181 @@ impl BinaryName {
     pub fn filename(&self) -> String {
         String::from(match &self {
             // Ansible => "ansible",
-            // Aws => "aws",
-            // Azure => "?",
+            Aws => "aws",
+            Azure => "az",
             // Docker => "docker",
+            Doctl => "doctl",
             Gcloud => "gcloud",
-            // Packer => "packer",
+            Git => "git",
+            Packer => "packer",
             // Terraform => "terraform",
         })
     }
+
+    /// Env var set to the path of the binary to use instead of the one found on PATH.
+    pub fn env_var(&self) -> String {
+        format!("SWOON_{}_PATH", self.filename().to_uppercase())
+    }
+
+    /// The swoon command or cloud platform that runs the binary.
+    fn required_by(&self) -> &'static str {
+        match &self {
+            Aws => "the aws platform",
+            Azure => "the azure platform",
+            Doctl => "the digitalocean platform",
+            Gcloud => "the gcp platform",
+            Git => "image provenance and git_sha image naming",
+            Packer => "swoon bake",
+        }
+    }
+
+    fn install_docs(&self) -> &'static str {
+        match &self {
+            Aws => "https://docs.aws.amazon.com/cli/latest/userguide/getting-started-install.html",
+            Azure => "https://docs.microsoft.com/cli/azure/install-azure-cli",
+            Doctl => "https://docs.digitalocean.com/reference/doctl/how-to/install/",
+            Gcloud => "https://cloud.google.com/sdk/docs/install",
+            Git => "https://git-scm.com/downloads",
+            Packer => "https://www.packer.io/downloads",
+        }
+    }
+}
+
+/// A binary that swoon needs to run and could not find on PATH or at its configured path.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BinaryNotFound {
+    pub binary: BinaryName,
+    /// Path configured with an env var or swoon.yml that is not a file, and where it was configured.
+    pub configured_path: Option<(PathBuf, String)>,
 }
 
+impl fmt::Display for BinaryNotFound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let filename = self.binary.filename();
+        match &self.configured_path {
+            None => write!(f, "{} is required by {} and was not found on PATH", filename, self.binary.required_by())?,
+            Some((path, source)) => write!(f, "{} is required by {} and {} from {} is not a file",
+                                           filename, self.binary.required_by(), path.display(), source)?,
+        }
+        write!(f, "\nInstall {} from {} or set its path with {} or binaries.{} in swoon.yml",
+               filename, self.binary.install_docs(), self.binary.env_var(), filename)
+    }
+}
+
+impl From<BinaryNotFound> for task::Error {
+    fn from(e: BinaryNotFound) -> Self {
+        Self::new(e.to_string())
+    }
+}
+
+pub type LookupResult = Result<PathBuf, BinaryNotFound>;
+
 pub trait PathLookup {
-    // fn ansible_path(&self) -> PathBuf {
+    // fn ansible_path(&self) -> LookupResult {
     //     self.lookup(Ansible)
     // }
 
-    // fn aws_path(&self) -> PathBuf {
-    //     self.lookup(Aws)
-    // }
+    fn aws_path(&self) -> LookupResult {
+        self.lookup(Aws)
+    }
 
-    // fn azure_path(&self) -> PathBuf {
-    //     self.lookup(Azure)
-    // }
+    fn azure_path(&self) -> LookupResult {
+        self.lookup(Azure)
+    }
 
-    // fn docker_path(&self) -> PathBuf {
+    // fn docker_path(&self) -> LookupResult {
     //     self.lookup(Docker)
     // }
 
-    fn gcloud_path(&self) -> PathBuf {
+    fn doctl_path(&self) -> LookupResult {
+        self.lookup(Doctl)
+    }
+
+    fn gcloud_path(&self) -> LookupResult {
         self.lookup(Gcloud)
     }
 
-    fn lookup(&self, bin: BinaryName) -> PathBuf;
+    fn git_path(&self) -> LookupResult {
+        self.lookup(Git)
+    }
 
-    // fn packer_path(&self) -> PathBuf {
-    //     self.lookup(Packer)
-    // }
+    fn lookup(&self, bin: BinaryName) -> LookupResult;
+
+    fn packer_path(&self) -> LookupResult {
+        self.lookup(Packer)
+    }
 
-    // fn terraform_path(&self) -> PathBuf {
+    // fn terraform_path(&self) -> LookupResult {
     //     self.lookup(Terraform)
     // }
 }
 
+/// Paths of the binaries swoon runs, from their env var, binaries in swoon.yml or PATH in that order.
 #[derive(Clone)]
 pub struct BinaryPaths {
-    paths: HashMap<BinaryName, PathBuf>,
+    paths: HashMap<BinaryName, LookupResult>,
 }
 
 impl BinaryPaths {
-    pub fn init() -> Self {
-        let mut paths = HashMap::new();
-        BinaryName::all().iter().for_each(|b| {
-            let path = which(b.filename());
-            if path.is_ok() {
-                paths.insert(b.clone(), path.unwrap());
-            }
-        });
-
+    pub fn init(config_opt: &Option<SwoonConfig>) -> Self {
+        let paths = BinaryName::all().into_iter()
+            .map(|b| {
+                let env_path = env::var(b.env_var()).ok().filter(|path| !path.is_empty());
+                let config_path = config_opt.as_ref().and_then(|cfg| cfg.binaries.path(&b));
+                let path = Self::resolve(&b, env_path, config_path);
+                (b, path)
+            })
+            .collect();
         Self { paths }
     }
+
+    fn resolve(bin: &BinaryName, env_path: Option<String>, config_path: Option<&PathBuf>) -> LookupResult {
+        let configured_path = match (env_path, config_path) {
+            (Some(path), _) => Some((PathBuf::from(path), bin.env_var())),
+            (None, Some(path)) => Some((ProjectDir::path().join(path), "swoon.yml".to_string())),
+            (None, None) => None,
+        };
+        match configured_path {
+            Some((path, _)) if path.is_file() => Ok(path),
+            Some(_) => Err(BinaryNotFound {
+                binary: bin.clone(),
+                configured_path,
+            }),
+            None => which(bin.filename()).map_err(|_| BinaryNotFound {
+                binary: bin.clone(),
+                configured_path: None,
+            }),
+        }
+    }
 }
 
 impl PathLookup for BinaryPaths {
-    fn lookup(&self, bin: BinaryName) -> PathBuf {
-        self.paths.get(&bin).expect("asdf").clone()
+    fn lookup(&self, bin: BinaryName) -> LookupResult {
+        self.paths.get(&bin).cloned().unwrap_or(Err(BinaryNotFound {
+            binary: bin,
+            configured_path: None,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_prefers_env_path() {
+        let env_path = env::current_exe().unwrap();
+        let config_path = PathBuf::from("/not/gcloud");
+        assert_eq!(BinaryPaths::resolve(&Gcloud, Some(env_path.to_string_lossy().to_string()), Some(&config_path)),
+                   Ok(env_path));
+    }
+
+    #[test]
+    fn test_resolve_missing_configured_path() {
+        let config_path = PathBuf::from("/not/gcloud");
+        let e = BinaryPaths::resolve(&Gcloud, None, Some(&config_path)).unwrap_err();
+        assert_eq!(e.configured_path, Some((config_path, "swoon.yml".to_string())));
+        assert_eq!(e.to_string(), "gcloud is required by the gcp platform and /not/gcloud from swoon.yml is not a file\n\
+            Install gcloud from https://cloud.google.com/sdk/docs/install or set its path with SWOON_GCLOUD_PATH or binaries.gcloud in swoon.yml");
     }
 }
@@ -1,23 +1,301 @@
 use std::fs;
 use std::path::{Path, PathBuf};
 
-use yaml_rust::YamlLoader;
-
 use crate::api::{CloudPlatform, DEFAULT_OS, OperatingSystem, task};
+use crate::api::binaries::BinaryName;
 use crate::api::output::file::{Directory, File};
 use crate::api::output::template::{Template, template_object, TemplateFile};
 use crate::api::util::ProjectDir;
+use crate::api::yaml::{ConfigErrors, YamlNode};
+use crate::images::ARCHETYPE_LABEL;
+use crate::platforms::{external, PlatformConfigs, provider};
 
 #[derive(Clone)]
 pub struct SwoonConfig {
     pub org_name: String,
     pub default_os: OperatingSystem,
     pub default_platform: CloudPlatform,
+    pub archetype: ArchetypeConfig,
+    pub environments: Vec<EnvironmentConfig>,
+    /// The environment selected with --env or SWOON_ENV.
+    pub environment: Option<EnvironmentConfig>,
+    pub image_naming: ImageNamingConfig,
+    pub image_retention: ImageRetentionConfig,
+    pub instances: Vec<InstanceConfig>,
+    /// Bake archetypes from the exact OS images locked in swoon.lock instead of OS image families.
+    pub pin_source_images: bool,
+    pub platforms: PlatformConfigs,
+    pub binaries: BinariesConfig,
+}
+
+/// Platforms and operating systems the archetype image is baked for.
+#[derive(Clone)]
+pub struct ArchetypeConfig {
+    pub platforms: Vec<CloudPlatform>,
+    pub operating_systems: Vec<OperatingSystem>,
+}
+
+impl ArchetypeConfig {
+    pub fn from_defaults(default_platform: &CloudPlatform, default_os: &OperatingSystem) -> Self {
+        Self {
+            platforms: vec!(default_platform.clone()),
+            operating_systems: vec!(default_os.clone()),
+        }
+    }
+
+    fn parse(yaml: &YamlNode,
+             errors: &mut ConfigErrors,
+             default_platform: &CloudPlatform,
+             default_os: &OperatingSystem) -> Self {
+        let mut archetype = Self::from_defaults(default_platform, default_os);
+        yaml.mapping(&["platforms", "operating_systems"], errors);
+        let platforms = yaml.get("platforms");
+        if !platforms.is_missing() {
+            archetype.platforms = platforms.sequence(errors).iter()
+                .filter_map(|platform| platform.parse_string(errors, external::parse_configured_platform))
+                .collect();
+            if archetype.platforms.is_empty() {
+                errors.add(&platforms, "must list at least one cloud platform");
+                archetype.platforms.push(default_platform.clone());
+            }
+        }
+        let operating_systems = yaml.get("operating_systems");
+        if !operating_systems.is_missing() {
+            archetype.operating_systems = operating_systems.sequence(errors).iter()
+                .filter_map(|os| os.parse_string(errors, OperatingSystem::from_string))
+                .collect();
+            if archetype.operating_systems.is_empty() {
+                errors.add(&operating_systems, "must list at least one operating system");
+                archetype.operating_systems.push(default_os.clone());
+            }
+        }
+        archetype
+    }
+}
+
+/// A named environment like dev or prod that images are baked into, with the cloud project or
+/// account it lives in and settings that override the platform defaults.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EnvironmentConfig {
+    pub name: String,
+    /// GCP project, defaulting to the core/project of the gcloud config.
+    pub project: Option<String>,
+    /// AWS account id that the aws cli must be authed to.
+    pub account: Option<String>,
+    /// Region for AWS, Azure, DigitalOcean, Linode and Vultr.
+    pub region: Option<String>,
+    /// Zone for GCP.
+    pub zone: Option<String>,
+    /// Appended to the name prefix of every image so that environments don't share images.
+    pub name_suffix: Option<String>,
+}
+
+impl EnvironmentConfig {
+    fn parse(name: String, yaml: &YamlNode, errors: &mut ConfigErrors) -> Self {
+        yaml.mapping(&["project", "account", "region", "zone", "name_suffix"], errors);
+        Self {
+            name,
+            project: yaml.get("project").string(errors),
+            account: yaml.get("account").id_string(errors),
+            region: yaml.get("region").string(errors),
+            zone: yaml.get("zone").string(errors),
+            name_suffix: yaml.get("name_suffix").string(errors),
+        }
+    }
+}
+
+/// Suffix appended to image names so that every bake produces uniquely named images.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ImageVersionScheme {
+    BuildNumber,
+    Timestamp,
+}
+
+impl ImageVersionScheme {
+    fn from_str(s: &str) -> task::Result<Self> {
+        match s {
+            "build_number" => Ok(ImageVersionScheme::BuildNumber),
+            "timestamp" => Ok(ImageVersionScheme::Timestamp),
+            _ => task::Error::result(format!("{} is not one of timestamp or build_number", s)),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ImageNamingConfig {
+    pub version: ImageVersionScheme,
+    pub git_sha: bool,
+}
+
+impl ImageNamingConfig {
+    pub fn default() -> Self {
+        Self {
+            version: ImageVersionScheme::Timestamp,
+            git_sha: false,
+        }
+    }
+
+    fn parse(yaml: &YamlNode, errors: &mut ConfigErrors) -> Self {
+        let mut image_naming = Self::default();
+        yaml.mapping(&["version", "git_sha"], errors);
+        if let Some(version) = yaml.get("version").parse_string(errors, ImageVersionScheme::from_str) {
+            image_naming.version = version;
+        }
+        if let Some(git_sha) = yaml.get("git_sha").bool(errors) {
+            image_naming.git_sha = git_sha;
+        }
+        image_naming
+    }
+}
+
+/// Paths of the clis swoon runs, used instead of the executables found on PATH.
+#[derive(Clone)]
+pub struct BinariesConfig {
+    paths: Vec<(BinaryName, PathBuf)>,
+}
+
+impl BinariesConfig {
+    pub fn default() -> Self {
+        Self {
+            paths: Vec::new(),
+        }
+    }
+
+    fn parse(yaml: &YamlNode, errors: &mut ConfigErrors) -> Self {
+        let filenames: Vec<String> = BinaryName::all().iter().map(|b| b.filename()).collect();
+        yaml.mapping(&filenames.iter().map(|filename| filename.as_str()).collect::<Vec<&str>>(), errors);
+        Self {
+            paths: BinaryName::all().into_iter()
+                .filter_map(|b| yaml.get(&b.filename()).string(errors).map(|path| (b, PathBuf::from(path))))
+                .collect(),
+        }
+    }
+
+    pub fn path(&self, bin: &BinaryName) -> Option<&PathBuf> {
+        self.paths.iter().find(|(b, _)| b == bin).map(|(_, path)| path)
+    }
+}
+
+/// State that images replaced by a newer image of their family are marked with.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DeprecationState {
+    Deprecated,
+    Obsolete,
+}
+
+impl DeprecationState {
+    fn from_str(s: &str) -> task::Result<Self> {
+        match s {
+            "deprecated" => Ok(DeprecationState::Deprecated),
+            "obsolete" => Ok(DeprecationState::Obsolete),
+            _ => task::Error::result(format!("{} is not one of deprecated or obsolete", s)),
+        }
+    }
+
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            DeprecationState::Deprecated => "DEPRECATED",
+            DeprecationState::Obsolete => "OBSOLETE",
+        }
+    }
+}
+
+/// Policy for deleting old images with `swoon images prune` and for the images of each
+/// image family that are deprecated or deleted after every successful `swoon bake`.
+#[derive(Clone)]
+pub struct ImageRetentionConfig {
+    pub max_age_days: Option<u32>,
+    /// Number of images kept in each image family, counting the newest image.
+    pub keep: Option<u32>,
+    pub deprecate: Option<DeprecationState>,
+}
+
+impl ImageRetentionConfig {
+    pub fn default() -> Self {
+        Self {
+            max_age_days: None,
+            keep: None,
+            deprecate: None,
+        }
+    }
+
+    fn parse(yaml: &YamlNode, errors: &mut ConfigErrors) -> Self {
+        yaml.mapping(&["max_age_days", "keep", "deprecate"], errors);
+        Self {
+            max_age_days: yaml.get("max_age_days").positive_u32("days", errors),
+            keep: yaml.get("keep").positive_u32("images", errors),
+            deprecate: yaml.get("deprecate").parse_string(errors, DeprecationState::from_str),
+        }
+    }
+
+    /// Whether bakes are followed by deprecating or deleting the older images of each baked image's family.
+    pub fn has_lifecycle(&self) -> bool {
+        self.keep.is_some() || self.deprecate.is_some()
+    }
+}
+
+/// An instance image provisioned by its own playbook on top of the archetype image.
+#[derive(Clone)]
+pub struct InstanceConfig {
+    pub name: String,
+    pub os: OperatingSystem,
+    pub playbook: String,
+}
+
+impl InstanceConfig {
+    /// Parses an instance, reporting names already used by the archetype or a preceding instance.
+    fn parse(yaml: &YamlNode, errors: &mut ConfigErrors, archetype: &ArchetypeConfig, preceding: &[InstanceConfig]) -> Self {
+        if !yaml.mapping(&["name", "os", "playbook"], errors) && !yaml.is_missing() {
+            return Self::invalid();
+        }
+        let name_yaml = yaml.get("name");
+        let name = name_yaml.required_string(errors).unwrap_or_default();
+        if name == ARCHETYPE_LABEL {
+            errors.add(&name_yaml, format!("{} is the name of the archetype image", name));
+        } else if preceding.iter().any(|instance| instance.name == name) {
+            errors.add(&name_yaml, format!("{} is already the name of an instance", name));
+        }
+        let os_yaml = yaml.get("os");
+        let os = match os_yaml.parse_string(errors, OperatingSystem::from_string) {
+            None => archetype.operating_systems[0].clone(),
+            Some(os) => {
+                if !archetype.operating_systems.contains(&os) {
+                    errors.add(&os_yaml, format!("{} is not in archetype operating_systems", os));
+                }
+                os
+            }
+        };
+        let playbook = yaml.get("playbook").string(errors)
+            .unwrap_or_else(|| format!("{}.yml", name));
+        let unsupported: Vec<&str> = archetype.platforms.iter()
+            .filter(|platform| !provider::supports_instance_images(platform))
+            .map(|platform| platform.to_str())
+            .collect();
+        if !unsupported.is_empty() {
+            errors.add(yaml, format!("instance images are not supported on {}", unsupported.join(" or ")));
+        }
+        Self {
+            name,
+            os,
+            playbook,
+        }
+    }
+
+    /// Placeholder for an instance that is not a mapping, with its error already reported.
+    fn invalid() -> Self {
+        Self {
+            name: String::new(),
+            os: DEFAULT_OS,
+            playbook: String::new(),
+        }
+    }
 }
 
 impl SwoonConfig {
+    const FILENAME: &'static str = "swoon.yml";
+
     pub fn config_file_path() -> PathBuf {
-        ProjectDir::path().join("swoon.yml")
+        ProjectDir::path().join(Self::FILENAME)
     }
 
     pub fn read_from_current_dir() -> task::Result<Option<SwoonConfig>> {
@@ -30,27 +308,68 @@ impl SwoonConfig {
         Ok(config)
     }
 
+    /// Parses swoon.yml, reporting every invalid or unknown field with its line and column.
     fn parse(config: &str) -> task::Result<SwoonConfig> {
-        let yaml_read = YamlLoader::load_from_str(config);
-        if let Err(e) = yaml_read {
-            return task::Error::result(e.to_string());
-        }
-        let yaml_docs = yaml_read.unwrap();
-        let doc = &yaml_docs[0];
-        let org_name = doc["org_name"].as_str()
-            .expect("org_name is a required swoon.yml field").to_string();
-        let default_platform = CloudPlatform::GCP;
-        let default_os = match doc["default_os"].as_str() {
-            None => DEFAULT_OS,
-            Some(s) => OperatingSystem::from_string(s)?,
-        };
-        Ok(SwoonConfig {
+        let doc = YamlNode::load(Self::FILENAME, config)?;
+        let mut errors = ConfigErrors::new(Self::FILENAME);
+        doc.mapping(&[
+            "org_name",
+            "default_os",
+            "default_platform",
+            "archetype",
+            "environments",
+            "image_naming",
+            "image_retention",
+            "instances",
+            "pin_source_images",
+            "platforms",
+            "binaries",
+        ], &mut errors);
+        let org_name = doc.get("org_name").required_string(&mut errors).unwrap_or_default();
+        let default_platform = doc.get("default_platform").parse_string(&mut errors, external::parse_configured_platform)
+            .unwrap_or(CloudPlatform::GCP);
+        let default_os = doc.get("default_os").parse_string(&mut errors, OperatingSystem::from_string)
+            .unwrap_or(DEFAULT_OS);
+        let archetype = ArchetypeConfig::parse(&doc.get("archetype"), &mut errors, &default_platform, &default_os);
+        let environments = doc.get("environments").entries(&mut errors).into_iter()
+            .map(|(name, environment)| EnvironmentConfig::parse(name, &environment, &mut errors))
+            .collect();
+        let image_naming = ImageNamingConfig::parse(&doc.get("image_naming"), &mut errors);
+        let image_retention = ImageRetentionConfig::parse(&doc.get("image_retention"), &mut errors);
+        let mut instances = Vec::new();
+        for instance in doc.get("instances").sequence(&mut errors) {
+            instances.push(InstanceConfig::parse(&instance, &mut errors, &archetype, &instances));
+        }
+        let pin_source_images = doc.get("pin_source_images").bool(&mut errors).unwrap_or(false);
+        let platforms = PlatformConfigs::parse(&doc.get("platforms"), &mut errors);
+        let binaries = BinariesConfig::parse(&doc.get("binaries"), &mut errors);
+        errors.result(SwoonConfig {
             org_name,
             default_os,
             default_platform,
+            archetype,
+            environments,
+            environment: None,
+            image_naming,
+            image_retention,
+            instances,
+            pin_source_images,
+            platforms,
+            binaries,
         })
     }
 
+    /// Selects the environment to bake images into and manage images of.
+    pub fn with_environment(self, name: &str) -> task::Result<Self> {
+        match self.environments.iter().find(|environment| environment.name == name) {
+            None => task::Error::result(format!("environment {} is not in swoon.yml environments", name)),
+            Some(environment) => Ok(Self {
+                environment: Some(environment.clone()),
+                ..self
+            }),
+        }
+    }
+
     pub fn read<P: AsRef<Path>>(config_path: P) -> task::Result<SwoonConfig> {
         let config_read = fs::read_to_string(config_path)?;
         Self::parse(config_read.as_ref())
@@ -81,7 +400,10 @@ impl TemplateFile for SwoonConfigFile {
     }
 
     fn template(&self) -> task::Result<Template> {
-        Template::new(include_bytes!("swoon.yml.liquid"))
+        match self.tmpl_name.as_str() {
+            "default" => Template::new(include_bytes!("swoon.yml.liquid")),
+            _ => task::Error::result(format!("swoon.yml template {} does not exist", self.tmpl_name)),
+        }
     }
 
     fn template_output_path(&self) -> (Directory, String) {
@@ -91,6 +413,8 @@ impl TemplateFile for SwoonConfigFile {
 
 #[cfg(test)]
 mod tests {
+    use crate::api::{DEBIAN_10, DEBIAN_11, UBUNTU_2004};
+
     use super::*;
 
     #[test]
@@ -110,7 +434,234 @@ mod tests {
         ";
         let config = SwoonConfig::parse(config_str).unwrap();
         assert_eq!(config.org_name, "eighty4");
-        assert_eq!(config.default_platform, CloudPlatform::GCP);
+        assert_eq!(config.default_platform, CloudPlatform::AWS);
         assert_eq!(config.default_os, OperatingSystem::Debian { version: 9 });
     }
+
+    #[test]
+    fn test_parse_binaries_config() {
+        let config = SwoonConfig::parse("---\norg_name: eighty4\nbinaries:\n  gcloud: /opt/google-cloud-sdk/bin/gcloud").unwrap();
+        assert_eq!(config.binaries.path(&BinaryName::Gcloud), Some(&PathBuf::from("/opt/google-cloud-sdk/bin/gcloud")));
+        assert_eq!(config.binaries.path(&BinaryName::Packer), None);
+        assert!(SwoonConfig::parse("---\norg_name: eighty4\nbinaries:\n  terraform: /usr/bin/terraform").is_err());
+    }
+
+    #[test]
+    fn test_parse_config_reports_every_error() {
+        let config_str = "---
+default_platform: gce
+archetpye:
+  platforms: [gcp]
+instances:
+  - name: postgres
+    os: ubuntu:20.04
+";
+        let error = SwoonConfig::parse(config_str).err().unwrap();
+        assert_eq!(error.msg, "swoon.yml line 3 column 1 archetpye: unknown key, expected one of org_name, \
+            default_os, default_platform, archetype, environments, image_naming, image_retention, instances, pin_source_images, \
+            platforms, binaries\n\
+            swoon.yml line 2 column 1 org_name: is required\n\
+            swoon.yml line 2 column 19 default_platform: cloud platform gce is not one of aws, azure, \
+            digitalocean, gcp, linode or vultr and there is no swoon-platform-gce executable on PATH\n\
+            swoon.yml line 7 column 9 instances[0].os: ubuntu:20.04 is not in archetype operating_systems");
+    }
+
+    #[test]
+    fn test_parse_archetype_config() {
+        let config_str = r"---
+        org_name: eighty4
+        archetype:
+          platforms:
+            - gcp
+            - aws
+          operating_systems:
+            - debian:11
+            - ubuntu:20.04
+        ";
+        let config = SwoonConfig::parse(config_str).unwrap();
+        assert_eq!(config.archetype.platforms, vec!(CloudPlatform::GCP, CloudPlatform::AWS));
+        assert_eq!(config.archetype.operating_systems, vec!(DEBIAN_11, UBUNTU_2004));
+    }
+
+    #[test]
+    fn test_parse_archetype_config_defaults() {
+        let config = SwoonConfig::parse("---\norg_name: eighty4\ndefault_os: debian:10").unwrap();
+        assert_eq!(config.archetype.platforms, vec!(CloudPlatform::GCP));
+        assert_eq!(config.archetype.operating_systems, vec!(DEBIAN_10));
+    }
+
+    #[test]
+    fn test_parse_image_naming_config() {
+        let config_str = r"---
+        org_name: eighty4
+        image_naming:
+          version: build_number
+          git_sha: true
+        ";
+        let config = SwoonConfig::parse(config_str).unwrap();
+        assert_eq!(config.image_naming.version, ImageVersionScheme::BuildNumber);
+        assert!(config.image_naming.git_sha);
+        let config = SwoonConfig::parse("---\norg_name: eighty4").unwrap();
+        assert_eq!(config.image_naming.version, ImageVersionScheme::Timestamp);
+        assert!(!config.image_naming.git_sha);
+        assert!(SwoonConfig::parse("---\norg_name: eighty4\nimage_naming:\n  version: semver").is_err());
+    }
+
+    #[test]
+    fn test_parse_image_retention_config() {
+        let config = SwoonConfig::parse("---\norg_name: eighty4\nimage_retention:\n  max_age_days: 30").unwrap();
+        assert_eq!(config.image_retention.max_age_days, Some(30));
+        let config = SwoonConfig::parse("---\norg_name: eighty4").unwrap();
+        assert_eq!(config.image_retention.max_age_days, None);
+        assert!(!config.image_retention.has_lifecycle());
+        assert!(SwoonConfig::parse("---\norg_name: eighty4\nimage_retention:\n  max_age_days: 0").is_err());
+    }
+
+    #[test]
+    fn test_parse_image_lifecycle_config() {
+        let config_str = "---
+        org_name: eighty4
+        image_retention:
+          keep: 3
+          deprecate: obsolete
+        ";
+        let config = SwoonConfig::parse(config_str).unwrap();
+        assert_eq!(config.image_retention.keep, Some(3));
+        assert_eq!(config.image_retention.deprecate, Some(DeprecationState::Obsolete));
+        assert!(config.image_retention.has_lifecycle());
+        assert!(SwoonConfig::parse("---\norg_name: eighty4\nimage_retention:\n  deprecate: deleted").is_err());
+        assert!(SwoonConfig::parse("---\norg_name: eighty4\nimage_retention:\n  keep: 0").is_err());
+    }
+
+    #[test]
+    fn test_parse_pin_source_images_config() {
+        let config = SwoonConfig::parse("---\norg_name: eighty4\npin_source_images: true").unwrap();
+        assert!(config.pin_source_images);
+        let config = SwoonConfig::parse("---\norg_name: eighty4").unwrap();
+        assert!(!config.pin_source_images);
+    }
+
+    #[test]
+    fn test_parse_environments_config() {
+        let config_str = r"---
+        org_name: eighty4
+        environments:
+          dev:
+            project: eighty4-dev
+            name_suffix: dev
+          prod:
+            account: '123456789012'
+            region: us-east-2
+        ";
+        let config = SwoonConfig::parse(config_str).unwrap();
+        assert_eq!(config.environments.len(), 2);
+        assert_eq!(config.environments[0].name, "dev");
+        assert_eq!(config.environments[0].project, Some("eighty4-dev".to_string()));
+        assert_eq!(config.environments[1].account, Some("123456789012".to_string()));
+        assert!(config.environment.is_none());
+        let config = config.with_environment("prod").unwrap();
+        assert_eq!(config.environment.unwrap().region, Some("us-east-2".to_string()));
+        assert!(SwoonConfig::parse(config_str).unwrap().with_environment("staging").is_err());
+    }
+
+    #[test]
+    fn test_parse_environments_config_with_numeric_account() {
+        let config = SwoonConfig::parse("---\norg_name: eighty4\nenvironments:\n  prod:\n    account: 012345678901").unwrap();
+        assert_eq!(config.environments[0].account, Some("012345678901".to_string()));
+    }
+
+    #[test]
+    fn test_parse_instances_config() {
+        let config_str = r"---
+        org_name: eighty4
+        instances:
+          - name: postgres
+          - name: redis
+            playbook: playbooks/redis.yml
+        ";
+        let config = SwoonConfig::parse(config_str).unwrap();
+        assert_eq!(config.instances.len(), 2);
+        assert_eq!(config.instances[0].name, "postgres");
+        assert_eq!(config.instances[0].playbook, "postgres.yml");
+        assert_eq!(config.instances[0].os, DEFAULT_OS);
+        assert_eq!(config.instances[1].playbook, "playbooks/redis.yml");
+    }
+
+    #[test]
+    fn test_parse_instances_config_requires_archetype_os() {
+        let config_str = r"---
+        org_name: eighty4
+        instances:
+          - name: postgres
+            os: ubuntu:20.04
+        ";
+        assert!(SwoonConfig::parse(config_str).is_err());
+    }
+
+    #[test]
+    fn test_parse_instances_config_requires_unique_names() {
+        let config_str = r"---
+        org_name: eighty4
+        instances:
+          - name: postgres
+          - name: archetype
+          - name: postgres
+        ";
+        let error = SwoonConfig::parse(config_str).err().unwrap();
+        assert_eq!(error.msg, "swoon.yml line 5 column 19 instances[1].name: archetype is the name of the archetype image\n\
+            swoon.yml line 6 column 19 instances[2].name: postgres is already the name of an instance");
+    }
+
+    #[test]
+    fn test_parse_instances_config_requires_instance_image_platforms() {
+        let config_str = r"---
+        org_name: eighty4
+        archetype:
+          platforms: [gcp, vultr]
+        instances:
+          - name: postgres
+        ";
+        let error = SwoonConfig::parse(config_str).err().unwrap();
+        assert_eq!(error.msg, "swoon.yml line 6 column 13 instances[0]: instance images are not supported on vultr");
+    }
+
+    #[test]
+    fn test_parse_platforms_config() {
+        let config_str = r"---
+        org_name: eighty4
+        platforms:
+          digitalocean:
+            region: sfo3
+          gcp:
+            zone: us-west1-b
+            disk_size: 20
+            tags:
+              - packer
+            use_iap: true
+          linode:
+            plan: g6-standard-2
+        ";
+        let config = SwoonConfig::parse(config_str).unwrap();
+        assert_eq!(config.platforms.digitalocean.region, "sfo3");
+        assert_eq!(config.platforms.digitalocean.size, "s-1vcpu-1gb");
+        assert_eq!(config.platforms.gcp.zone, Some("us-west1-b".to_string()));
+        assert_eq!(config.platforms.gcp.machine_type, "e2-medium");
+        assert_eq!(config.platforms.gcp.disk_size, Some(20));
+        assert_eq!(config.platforms.gcp.tags, vec!("packer"));
+        assert!(config.platforms.gcp.use_iap);
+        assert!(!config.platforms.gcp.preemptible);
+        assert!(!config.platforms.gcp.spot);
+        assert_eq!(config.platforms.linode.plan, "g6-standard-2");
+        assert_eq!(config.platforms.linode.region, "us-east");
+        assert_eq!(config.platforms.vultr.region, "ewr");
+    }
+
+    #[test]
+    fn test_parse_gcp_spot_config() {
+        let config = SwoonConfig::parse("---\norg_name: eighty4\nplatforms:\n  gcp:\n    spot: true").unwrap();
+        assert!(config.platforms.gcp.spot);
+        assert!(!config.platforms.gcp.preemptible);
+        let error = SwoonConfig::parse("---\norg_name: eighty4\nplatforms:\n  gcp:\n    spot: true\n    preemptible: true").err().unwrap();
+        assert_eq!(error.msg, "swoon.yml line 5 column 5 platforms.gcp: preemptible and spot can not both be set");
+    }
 }
@@ -1,17 +1,19 @@
-use std::path::PathBuf;
+use std::env;
 
 use clap::ArgMatches;
 use console::Term;
 
-use crate::api::binaries::{BinaryName, BinaryPaths};
+use crate::api::binaries::{BinaryName, BinaryPaths, LookupResult};
 use crate::api::binaries::PathLookup;
-use crate::api::config::SwoonConfig;
+use crate::api::config::{EnvironmentConfig, SwoonConfig};
 use crate::api::task;
 use crate::platforms::PlatformContexts;
 
 #[derive(Clone)]
 pub struct SwoonOpts {
     pub debug: bool,
+    /// Environment from swoon.yml selected with --env or SWOON_ENV.
+    pub environment: Option<String>,
 }
 
 pub struct SwoonContext {
@@ -24,21 +26,36 @@ pub struct SwoonContext {
 
 impl SwoonContext {
     #[cfg(test)]
+    #[allow(dead_code)]
     pub fn default() -> task::Result<Self> {
         Self::init(SwoonOpts {
             debug: false,
+            environment: None,
         })
     }
 
     pub fn init_from_args(args: &ArgMatches) -> task::Result<Self> {
-        Self::init(SwoonOpts {
+        let environment = match args.value_of("env") {
+            Some(environment) => Some(environment.to_string()),
+            None => env::var("SWOON_ENV").ok().filter(|environment| !environment.is_empty()),
+        };
+        let ctx = Self::init(SwoonOpts {
             debug: args.is_present("debug"),
-        })
+            environment,
+        })?;
+        if let Some(environment) = args.value_of("env").filter(|_| !ctx.has_config()) {
+            return task::Error::result(format!(
+                "--env {} requires a swoon.yml file in your current directory", environment));
+        }
+        Ok(ctx)
     }
 
     pub fn init(opts: SwoonOpts) -> task::Result<Self> {
-        let binary_paths = BinaryPaths::init();
-        let config_opt = SwoonConfig::read_from_current_dir()?;
+        let config_opt = match (SwoonConfig::read_from_current_dir()?, &opts.environment) {
+            (Some(config), Some(environment)) => Some(config.with_environment(environment)?),
+            (config_opt, _) => config_opt,
+        };
+        let binary_paths = BinaryPaths::init(&config_opt);
         let platforms = PlatformContexts::init(&binary_paths, &config_opt);
         Ok(Self {
             binary_paths,
@@ -53,10 +70,15 @@ impl SwoonContext {
         self.config_opt.as_ref().expect("no config")
     }
 
+    pub fn environment(&self) -> Option<&EnvironmentConfig> {
+        self.config_opt.as_ref().and_then(|cfg| cfg.environment.as_ref())
+    }
+
     pub fn has_config(&self) -> bool {
         self.config_opt.is_some()
     }
 
+    #[allow(dead_code)]
     pub fn with_config(&self, new_config: SwoonConfig) -> Self {
         let config_opt = Some(new_config);
         let platforms = PlatformContexts::init(&self.binary_paths, &config_opt);
@@ -78,7 +100,7 @@ impl SwoonContext {
 }
 
 impl PathLookup for SwoonContext {
-    fn lookup(&self, bin: BinaryName) -> PathBuf {
+    fn lookup(&self, bin: BinaryName) -> LookupResult {
         self.binary_paths.lookup(bin)
     }
 }
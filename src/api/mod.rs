@@ -1,6 +1,8 @@
+use std::fmt;
 use std::hash::{Hash, Hasher};
 
 use crate::api::CloudPlatform::*;
+use crate::platforms::external;
 
 pub mod binaries;
 pub mod command;
@@ -10,30 +12,32 @@ pub mod output;
 pub mod process;
 pub mod task;
 pub mod util;
+pub mod yaml;
 
 pub const DEBIAN_11: OperatingSystem = OperatingSystem::Debian { version: 11 };
 pub const DEBIAN_10: OperatingSystem = OperatingSystem::Debian { version: 10 };
 pub const DEBIAN_9: OperatingSystem = OperatingSystem::Debian { version: 9 };
 
 pub const UBUNTU_2004: OperatingSystem = OperatingSystem::Ubuntu {
-    version: MajorMinorVersion { major: 20, minor: 04 },
+    version: MajorMinorVersion { major: 20, minor: 4 },
     minimal: false,
 };
 pub const UBUNTU_2004_MINIMAL: OperatingSystem = OperatingSystem::Ubuntu {
-    version: MajorMinorVersion { major: 20, minor: 04 },
+    version: MajorMinorVersion { major: 20, minor: 4 },
     minimal: true,
 };
 pub const UBUNTU_1804: OperatingSystem = OperatingSystem::Ubuntu {
-    version: MajorMinorVersion { major: 18, minor: 04 },
+    version: MajorMinorVersion { major: 18, minor: 4 },
     minimal: false,
 };
 pub const UBUNTU_1804_MINIMAL: OperatingSystem = OperatingSystem::Ubuntu {
-    version: MajorMinorVersion { major: 18, minor: 04 },
+    version: MajorMinorVersion { major: 18, minor: 4 },
     minimal: true,
 };
 
 pub const DEFAULT_OS: OperatingSystem = DEBIAN_11;
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Clone, Debug, PartialEq)]
 pub enum CloudPlatform {
     AWS,
@@ -42,18 +46,31 @@ pub enum CloudPlatform {
     GCP,
     Linode,
     Vultr,
+    /// A platform provided by a `swoon-platform-<name>` executable on PATH, which is resolved
+    /// when the platform's provider is initialized.
+    External(String),
 }
 
 impl CloudPlatform {
     pub fn from_str(label: &str) -> Self {
+        match Self::parse(label) {
+            Ok(platform) => platform,
+            Err(e) => task::fatal(e.msg),
+        }
+    }
+
+    pub fn parse(label: &str) -> task::Result<Self> {
         match label {
-            "aws" => AWS,
-            "azure" => Azure,
-            "digitalocean" => DigitalOcean,
-            "gcp" => GCP,
-            "linode" => Linode,
-            "vultr" => Vultr,
-            _ => task::fatal(format!("cloud platform {} invalid", label)),
+            "aws" => Ok(AWS),
+            "azure" => Ok(Azure),
+            "digitalocean" => Ok(DigitalOcean),
+            "gcp" => Ok(GCP),
+            "linode" => Ok(Linode),
+            "vultr" => Ok(Vultr),
+            _ if external::is_plugin_name(label) => Ok(External(label.to_string())),
+            _ => task::Error::result(format!(
+                "cloud platform {} is not one of aws, azure, digitalocean, gcp, linode or vultr \
+                or a plugin name of lowercase letters, digits and hyphens", label)),
         }
     }
 
@@ -65,6 +82,7 @@ impl CloudPlatform {
             GCP => "gcp",
             Linode => "linode",
             Vultr => "vultr",
+            External(name) => name,
         }
     }
 }
@@ -91,18 +109,35 @@ impl OperatingSystem {
             "ubuntu:minimal" | "ubuntu:20.04:minimal" | "ubuntu:focal:minimal" => Ok(UBUNTU_2004_MINIMAL),
             "ubuntu:18.04" | "ubuntu:bionic" => Ok(UBUNTU_1804),
             "ubuntu:18.04:minimal" | "ubuntu:bionic:minimal" => Ok(UBUNTU_1804_MINIMAL),
-            &_ => task::Error::result("invalid gcp family string"),
+            &_ => task::Error::result(format!("operating system {} is not supported", s)),
         }
     }
 
-    pub fn to_string(&self) -> String {
+    /// Lowercase, hyphenated identifier for use in image names and packer source labels.
+    pub fn slug(&self) -> String {
         match self {
-            OperatingSystem::Debian { version } => format!("debian:{}", version),
+            OperatingSystem::Debian { version } => format!("debian-{}", version),
             OperatingSystem::Ubuntu { version, minimal } => {
+                let slug = format!("ubuntu-{:02}{:02}", version.major, version.minor);
                 if *minimal {
-                    format!("ubuntu:{:02}.{:02}:minimal", version.major, version.minor)
+                    format!("{}-minimal", slug)
                 } else {
-                    format!("ubuntu:{:02}.{:02}", version.major, version.minor)
+                    slug
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for OperatingSystem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OperatingSystem::Debian { version } => write!(f, "debian:{}", version),
+            OperatingSystem::Ubuntu { version, minimal } => {
+                if *minimal {
+                    write!(f, "ubuntu:{:02}.{:02}:minimal", version.major, version.minor)
+                } else {
+                    write!(f, "ubuntu:{:02}.{:02}", version.major, version.minor)
                 }
             }
         }
@@ -125,6 +160,13 @@ impl Hash for OperatingSystem {
 mod test {
     use super::*;
 
+    #[test]
+    fn test_cloud_platform_parse() {
+        assert_eq!(CloudPlatform::parse("gcp"), Ok(GCP));
+        assert_eq!(CloudPlatform::parse("hetzner"), Ok(External("hetzner".to_string())));
+        assert!(CloudPlatform::parse("Hetzner").is_err());
+    }
+
     #[test]
     fn test_operating_system_from_string() {
         assert_eq!(OperatingSystem::from_string("debian"), Ok(DEBIAN_11));
@@ -162,4 +204,11 @@ mod test {
         assert_eq!("ubuntu:18.04", UBUNTU_1804.to_string());
         assert_eq!("ubuntu:20.04", UBUNTU_2004.to_string());
     }
+
+    #[test]
+    fn test_operating_system_slug() {
+        assert_eq!("debian-11", DEBIAN_11.slug());
+        assert_eq!("ubuntu-1804-minimal", UBUNTU_1804_MINIMAL.slug());
+        assert_eq!("ubuntu-2004", UBUNTU_2004.slug());
+    }
 }
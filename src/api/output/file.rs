@@ -7,9 +7,11 @@ use Directory::*;
 use crate::api::task;
 use crate::api::util::{DataDir, ProjectDir};
 
+#[allow(dead_code)]
 pub struct Batch {}
 
 impl Batch {
+    #[allow(dead_code)]
     pub fn write_all(files: Vec<&dyn File>) -> task::Result<()> {
         for file in files {
             file.write()?;
@@ -86,6 +88,7 @@ pub trait File {
     }
 }
 
+#[allow(dead_code)]
 pub fn write<S: AsRef<str>>(p: PathBuf, content: S) -> task::Result<()> {
     write_bytes(p, content.as_ref().as_bytes().to_vec())
 }
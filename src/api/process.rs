@@ -1,6 +1,7 @@
 use std::ffi::OsStr;
-use std::path::PathBuf;
-use std::process::{Command, Stdio};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
 
 use crate::api::task;
 use crate::api::util::ProjectDir;
@@ -15,16 +16,26 @@ impl Process {
         Self::invoke_from_dir(ProjectDir::path(), cmd, args)
     }
 
+    /// Whether a command exits successfully, for commands whose failure is an answer rather than an error.
+    pub fn succeeds<I, S>(cmd: &PathBuf, args: I) -> task::Result<bool>
+        where
+            I: IntoIterator<Item=S>,
+            S: AsRef<OsStr>, {
+        let status = Command::new(cmd)
+            .current_dir(ProjectDir::path())
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()?;
+        Ok(status.success())
+    }
+
     pub fn invoke_from_dir<I, S>(invoke_dir: PathBuf, cmd: &PathBuf, args: I) -> task::Result<String>
         where
             I: IntoIterator<Item=S>,
             S: AsRef<OsStr>, {
-        let mut debug_print_cmd = String::from(cmd.file_name().unwrap().to_str().unwrap());
-        let mut vec_copy_args = Vec::new();
-        args.into_iter().for_each(|s| {
-            debug_print_cmd.push_str(format!(" {}", s.as_ref().to_str().unwrap()).as_str());
-            vec_copy_args.push(s);
-        });
+        let (debug_print_cmd, vec_copy_args) = Self::debug_print_cmd(cmd, args);
 
         let output = Command::new(cmd)
             .current_dir(invoke_dir)
@@ -35,8 +46,7 @@ impl Process {
         if output.status.success() {
             Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
         } else {
-            let exit_code = output.status.code()
-                .map_or(String::from("?"), |c| c.to_string());
+            let exit_code = Self::exit_code(&output.status);
             println!("\n{}\nexit code {}\ncommand output:\n\n{}",
                      debug_print_cmd,
                      exit_code,
@@ -47,4 +57,77 @@ impl Process {
             task::Error::result(error_msg)
         }
     }
+
+    /// Runs a command with its stdout and stderr attached to the terminal so that
+    /// output from long running processes like packer is visible while it runs.
+    pub fn stream_from_dir<I, S>(invoke_dir: PathBuf,
+                                 cmd: &PathBuf,
+                                 args: I,
+                                 envs: Vec<(&str, &str)>) -> task::Result<()>
+        where
+            I: IntoIterator<Item=S>,
+            S: AsRef<OsStr>, {
+        let (debug_print_cmd, vec_copy_args) = Self::debug_print_cmd(cmd, args);
+
+        let status = Command::new(cmd)
+            .current_dir(invoke_dir)
+            .args(vec_copy_args)
+            .envs(envs)
+            .stdin(Stdio::null())
+            .stdout(Stdio::inherit())
+            .stderr(Stdio::inherit())
+            .status()?;
+        if status.success() {
+            task::SUCCESS
+        } else {
+            task::Error::result(format!("exit code {} invoking {}",
+                                        Self::exit_code(&status),
+                                        debug_print_cmd))
+        }
+    }
+
+    /// Writes input to the stdin of a process and returns its stdout, with its stderr attached
+    /// to the terminal so that the process can report progress and errors while it runs.
+    pub fn exchange<I, S>(cmd: &PathBuf, args: I, input: &str) -> task::Result<String>
+        where
+            I: IntoIterator<Item=S>,
+            S: AsRef<OsStr>, {
+        let (debug_print_cmd, vec_copy_args) = Self::debug_print_cmd(cmd, args);
+
+        let mut child = Command::new(cmd)
+            .current_dir(ProjectDir::path())
+            .args(vec_copy_args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(input.as_bytes())?;
+        }
+        let output = child.wait_with_output()?;
+        if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+        } else {
+            task::Error::result(format!("exit code {} invoking {}",
+                                        Self::exit_code(&output.status),
+                                        debug_print_cmd))
+        }
+    }
+
+    fn debug_print_cmd<I, S>(cmd: &Path, args: I) -> (String, Vec<S>)
+        where
+            I: IntoIterator<Item=S>,
+            S: AsRef<OsStr>, {
+        let mut debug_print_cmd = String::from(cmd.file_name().unwrap().to_str().unwrap());
+        let mut vec_copy_args = Vec::new();
+        args.into_iter().for_each(|s| {
+            debug_print_cmd.push_str(format!(" {}", s.as_ref().to_str().unwrap()).as_str());
+            vec_copy_args.push(s);
+        });
+        (debug_print_cmd, vec_copy_args)
+    }
+
+    fn exit_code(status: &ExitStatus) -> String {
+        status.code().map_or(String::from("?"), |c| c.to_string())
+    }
 }
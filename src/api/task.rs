@@ -24,7 +24,7 @@ impl Error {
     }
 
     pub fn exit(&self) -> ! {
-        println!("{}", self.to_string());
+        println!("{}", self);
         process::exit(1);
     }
 }
@@ -41,3 +41,9 @@ impl From<std::io::Error> for Error {
         Self::new(f.to_string())
     }
 }
+
+impl From<serde_json::Error> for Error {
+    fn from(f: serde_json::Error) -> Self {
+        Self::new(f.to_string())
+    }
+}
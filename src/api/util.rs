@@ -1,4 +1,7 @@
-use std::{env, fs, path::PathBuf};
+use std::{env, fs, path::{Path, PathBuf}};
+
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
 
 use crate::api::task;
 
@@ -7,6 +10,7 @@ pub struct DataDir {}
 impl DataDir {
     const DEFAULT_PATH: &'static str = "./.swoon";
 
+    #[allow(dead_code)]
     pub fn create_sub_dir(path: &str) -> task::Result<PathBuf> {
         let path = Self::sub_dir_path(path);
         fs::create_dir_all(&path)?;
@@ -35,6 +39,34 @@ impl ProjectDir {
     }
 }
 
+pub fn copy_dir(from: &Path, to: &Path) -> task::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let path = entry.path();
+        if path.is_dir() {
+            copy_dir(&path, &to.join(entry.file_name()))?;
+        } else {
+            fs::copy(&path, to.join(entry.file_name()))?;
+        }
+    }
+    task::SUCCESS
+}
+
+pub fn format_timestamp(timestamp: &OffsetDateTime) -> task::Result<String> {
+    match timestamp.format(&Rfc3339) {
+        Ok(s) => Ok(s),
+        Err(e) => task::Error::result(e.to_string()),
+    }
+}
+
+pub fn parse_timestamp(s: &str) -> task::Result<OffsetDateTime> {
+    match OffsetDateTime::parse(s, &Rfc3339) {
+        Ok(timestamp) => Ok(timestamp),
+        Err(e) => task::Error::result(format!("{} is not a valid timestamp: {}", s, e)),
+    }
+}
+
 pub fn split_string(split: &str, string: String) -> Vec<String> {
     if string.is_empty() {
         vec!()
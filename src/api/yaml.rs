@@ -0,0 +1,459 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use yaml_rust::{Event, Yaml};
+use yaml_rust::parser::{MarkedEventReceiver, Parser};
+use yaml_rust::scanner::{Marker, TScalarStyle};
+
+use crate::api::task;
+
+/// A yaml value with the line and column it was read from and its field path in the document,
+/// so that config errors can point at the problem.
+#[derive(Clone, Debug)]
+pub struct YamlNode {
+    path: String,
+    line: usize,
+    col: usize,
+    value: YamlValue,
+}
+
+#[derive(Clone, Debug)]
+enum YamlValue {
+    /// A key that is not in its mapping, positioned at the mapping.
+    Missing,
+    /// A scalar with the text it was written as.
+    Scalar(Yaml, String),
+    Sequence(Vec<YamlNode>),
+    Mapping(Vec<MappingEntry>),
+}
+
+#[derive(Clone, Debug)]
+struct MappingEntry {
+    key: String,
+    line: usize,
+    col: usize,
+    value: YamlNode,
+}
+
+impl YamlNode {
+    /// Loads the first document of a yaml source. Later documents are ignored.
+    pub fn load(filename: &str, source: &str) -> task::Result<Self> {
+        let mut loader = NodeLoader {
+            docs: Vec::new(),
+            stack: Vec::new(),
+            anchors: HashMap::new(),
+            errors: ConfigErrors::new(filename),
+        };
+        if let Err(e) = Parser::new(source.chars()).load(&mut loader, false) {
+            return task::Error::result(format!("{} is not valid yaml: {}", filename, e));
+        }
+        let node = loader.docs.into_iter().next().unwrap_or(YamlNode {
+            path: String::new(),
+            line: 1,
+            col: 0,
+            value: YamlValue::Missing,
+        });
+        loader.errors.result(node)
+    }
+
+    pub fn is_missing(&self) -> bool {
+        matches!(self.value, YamlValue::Missing | YamlValue::Scalar(Yaml::Null, _))
+    }
+
+    /// The value of a key, or a missing node when the key or the mapping is not present.
+    pub fn get(&self, key: &str) -> YamlNode {
+        let path = if self.path.is_empty() { key.to_string() } else { format!("{}.{}", self.path, key) };
+        let entry = match &self.value {
+            YamlValue::Mapping(entries) => entries.iter().find(|entry| entry.key == key),
+            _ => None,
+        };
+        match entry {
+            Some(entry) => YamlNode {
+                path,
+                ..entry.value.clone()
+            },
+            None => YamlNode {
+                path,
+                line: self.line,
+                col: self.col,
+                value: YamlValue::Missing,
+            },
+        }
+    }
+
+    /// Checks that the node is a mapping of only the given keys. Returns false when the node is
+    /// missing or is not a mapping.
+    pub fn mapping(&self, keys: &[&str], errors: &mut ConfigErrors) -> bool {
+        match &self.value {
+            YamlValue::Mapping(entries) => {
+                for entry in entries {
+                    if !keys.contains(&entry.key.as_str()) {
+                        errors.add_at(entry.line, entry.col, &self.get(&entry.key).path,
+                                      format!("unknown key, expected one of {}", keys.join(", ")));
+                    }
+                }
+                true
+            }
+            _ if self.is_missing() => false,
+            _ => {
+                errors.add(self, "must be a mapping");
+                false
+            }
+        }
+    }
+
+    /// Keys of a mapping, or none when the node is not a mapping.
+    pub fn keys(&self) -> Vec<String> {
+        match &self.value {
+            YamlValue::Mapping(entries) => entries.iter().map(|entry| entry.key.clone()).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Converts the node to json for settings that swoon passes through without parsing.
+    pub fn to_json(&self) -> serde_json::Value {
+        match &self.value {
+            YamlValue::Scalar(Yaml::String(s), _) => serde_json::Value::from(s.as_str()),
+            YamlValue::Scalar(Yaml::Integer(i), _) => serde_json::Value::from(*i),
+            YamlValue::Scalar(Yaml::Real(r), _) => r.parse::<f64>().map_or(serde_json::Value::from(r.as_str()), serde_json::Value::from),
+            YamlValue::Scalar(Yaml::Boolean(b), _) => serde_json::Value::from(*b),
+            YamlValue::Scalar(..) | YamlValue::Missing => serde_json::Value::Null,
+            YamlValue::Sequence(items) => items.iter().map(|item| item.to_json()).collect(),
+            YamlValue::Mapping(entries) => serde_json::Value::Object(entries.iter()
+                .map(|entry| (entry.key.clone(), entry.value.to_json()))
+                .collect()),
+        }
+    }
+
+    /// Keys and values of a mapping of names to values, or none when the node is missing.
+    pub fn entries(&self, errors: &mut ConfigErrors) -> Vec<(String, YamlNode)> {
+        match &self.value {
+            YamlValue::Mapping(entries) => entries.iter()
+                .map(|entry| (entry.key.clone(), self.get(&entry.key)))
+                .collect(),
+            _ if self.is_missing() => Vec::new(),
+            _ => {
+                errors.add(self, "must be a mapping");
+                Vec::new()
+            }
+        }
+    }
+
+    /// Items of a sequence with their index in their path, or none when the node is missing.
+    pub fn sequence(&self, errors: &mut ConfigErrors) -> Vec<YamlNode> {
+        match &self.value {
+            YamlValue::Sequence(items) => items.iter().enumerate()
+                .map(|(i, item)| YamlNode {
+                    path: format!("{}[{}]", self.path, i),
+                    ..item.clone()
+                })
+                .collect(),
+            _ if self.is_missing() => Vec::new(),
+            _ => {
+                errors.add(self, "must be a list");
+                Vec::new()
+            }
+        }
+    }
+
+    pub fn string(&self, errors: &mut ConfigErrors) -> Option<String> {
+        match &self.value {
+            YamlValue::Scalar(Yaml::String(s), _) => Some(s.clone()),
+            _ if self.is_missing() => None,
+            _ => {
+                errors.add(self, "must be a string");
+                None
+            }
+        }
+    }
+
+    /// A string, or an unquoted number kept as the digits it was written with, like an AWS account id.
+    pub fn id_string(&self, errors: &mut ConfigErrors) -> Option<String> {
+        match &self.value {
+            YamlValue::Scalar(Yaml::Integer(_), text) => Some(text.clone()),
+            _ => self.string(errors),
+        }
+    }
+
+    pub fn required_string(&self, errors: &mut ConfigErrors) -> Option<String> {
+        if self.is_missing() {
+            errors.add(self, "is required");
+        }
+        self.string(errors)
+    }
+
+    pub fn bool(&self, errors: &mut ConfigErrors) -> Option<bool> {
+        match &self.value {
+            YamlValue::Scalar(Yaml::Boolean(b), _) => Some(*b),
+            _ if self.is_missing() => None,
+            _ => {
+                errors.add(self, "must be true or false");
+                None
+            }
+        }
+    }
+
+    /// A positive integer, where `unit` describes the number in the error for other values.
+    pub fn positive_u32(&self, unit: &str, errors: &mut ConfigErrors) -> Option<u32> {
+        match &self.value {
+            YamlValue::Scalar(Yaml::Integer(i), _) if *i > 0 && *i <= u32::MAX as i64 => Some(*i as u32),
+            _ if self.is_missing() => None,
+            _ => {
+                errors.add(self, format!("must be a positive number of {}", unit));
+                None
+            }
+        }
+    }
+
+    /// Parses a string with a fallible conversion, reporting its error at this node.
+    pub fn parse_string<T, F>(&self, errors: &mut ConfigErrors, parse: F) -> Option<T>
+        where F: FnOnce(&str) -> task::Result<T> {
+        match parse(&self.string(errors)?) {
+            Ok(value) => Some(value),
+            Err(e) => {
+                errors.add(self, e.msg);
+                None
+            }
+        }
+    }
+}
+
+/// Every problem found in a yaml document, reported together rather than one fix at a time.
+#[derive(Debug)]
+pub struct ConfigErrors {
+    filename: String,
+    errors: Vec<ConfigError>,
+}
+
+#[derive(Debug)]
+struct ConfigError {
+    line: usize,
+    col: usize,
+    path: String,
+    msg: String,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {} column {}", self.line, self.col + 1)?;
+        if !self.path.is_empty() {
+            write!(f, " {}", self.path)?;
+        }
+        write!(f, ": {}", self.msg)
+    }
+}
+
+impl ConfigErrors {
+    pub fn new(filename: &str) -> Self {
+        Self {
+            filename: filename.to_string(),
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn add<S: AsRef<str>>(&mut self, node: &YamlNode, msg: S) {
+        self.add_at(node.line, node.col, &node.path, msg);
+    }
+
+    fn add_at<S: AsRef<str>>(&mut self, line: usize, col: usize, path: &str, msg: S) {
+        self.errors.push(ConfigError {
+            line,
+            col,
+            path: path.to_string(),
+            msg: msg.as_ref().to_string(),
+        });
+    }
+
+    pub fn result<T>(self, value: T) -> task::Result<T> {
+        if self.errors.is_empty() {
+            Ok(value)
+        } else {
+            task::Error::result(self.errors.iter()
+                .map(|e| format!("{} {}", self.filename, e))
+                .collect::<Vec<String>>()
+                .join("\n"))
+        }
+    }
+}
+
+enum PartialNode {
+    Sequence { marker: Marker, anchor: usize, items: Vec<YamlNode> },
+    Mapping { marker: Marker, anchor: usize, entries: Vec<MappingEntry>, key: Option<(String, Marker)> },
+}
+
+/// Builds nodes from parser events the way yaml_rust::YamlLoader builds Yaml values,
+/// keeping the marker of every node and mapping key.
+struct NodeLoader {
+    docs: Vec<YamlNode>,
+    stack: Vec<PartialNode>,
+    anchors: HashMap<usize, YamlNode>,
+    errors: ConfigErrors,
+}
+
+impl NodeLoader {
+    fn node(marker: Marker, value: YamlValue) -> YamlNode {
+        YamlNode {
+            path: String::new(),
+            line: marker.line(),
+            col: marker.col(),
+            value,
+        }
+    }
+
+    fn insert(&mut self, node: YamlNode, anchor: usize) {
+        if anchor > 0 {
+            self.anchors.insert(anchor, node.clone());
+        }
+        match self.stack.last_mut() {
+            None => self.docs.push(node),
+            Some(PartialNode::Sequence { items, .. }) => items.push(node),
+            Some(PartialNode::Mapping { entries, key, .. }) => match key.take() {
+                Some((key, marker)) => {
+                    if entries.iter().any(|entry| entry.key == key) {
+                        self.errors.add_at(marker.line(), marker.col(), &key, "duplicate key");
+                    }
+                    entries.push(MappingEntry {
+                        key,
+                        line: marker.line(),
+                        col: marker.col(),
+                        value: node,
+                    });
+                }
+                None => self.errors.add_at(node.line, node.col, "", "mapping keys must be strings"),
+            },
+        }
+    }
+}
+
+impl MarkedEventReceiver for NodeLoader {
+    fn on_event(&mut self, ev: Event, marker: Marker) {
+        match ev {
+            Event::Scalar(value, style, anchor, _) => {
+                if let Some(PartialNode::Mapping { key: key @ None, .. }) = self.stack.last_mut() {
+                    *key = Some((value, marker));
+                    return;
+                }
+                let yaml = if style == TScalarStyle::Plain {
+                    Yaml::from_str(&value)
+                } else {
+                    Yaml::String(value.clone())
+                };
+                self.insert(Self::node(marker, YamlValue::Scalar(yaml, value)), anchor);
+            }
+            Event::SequenceStart(anchor) => self.stack.push(PartialNode::Sequence {
+                marker,
+                anchor,
+                items: Vec::new(),
+            }),
+            Event::MappingStart(anchor) => self.stack.push(PartialNode::Mapping {
+                marker,
+                anchor,
+                entries: Vec::new(),
+                key: None,
+            }),
+            Event::SequenceEnd | Event::MappingEnd => {
+                let (node, anchor) = match self.stack.pop() {
+                    Some(PartialNode::Sequence { marker, anchor, items }) =>
+                        (Self::node(marker, YamlValue::Sequence(items)), anchor),
+                    Some(PartialNode::Mapping { marker, anchor, entries, .. }) => {
+                        // a block mapping starts at its first key rather than where its event is marked
+                        let (line, col) = match entries.first() {
+                            Some(entry) => (entry.line, entry.col),
+                            None => (marker.line(), marker.col()),
+                        };
+                        (YamlNode { line, col, ..Self::node(marker, YamlValue::Mapping(entries)) }, anchor)
+                    }
+                    None => return,
+                };
+                self.insert(node, anchor);
+            }
+            Event::Alias(anchor) => {
+                let node = match self.anchors.get(&anchor) {
+                    Some(node) => node.clone(),
+                    None => Self::node(marker, YamlValue::Scalar(Yaml::Null, String::new())),
+                };
+                self.insert(node, 0);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error_msg<T>(result: task::Result<T>) -> String {
+        match result {
+            Ok(_) => panic!("expected an error"),
+            Err(e) => e.msg,
+        }
+    }
+
+    #[test]
+    fn test_load_marks_nodes() {
+        let doc = YamlNode::load("test.yml", "---\nfoo:\n  bar: baz\n  list:\n    - 1\n    - two\n").unwrap();
+        let mut errors = ConfigErrors::new("test.yml");
+        let items = doc.get("foo").get("list").sequence(&mut errors);
+        errors.add(&items[1], "is not a number");
+        errors.add(&doc.get("foo").get("qux"), "is required");
+        assert_eq!(error_msg(errors.result(())), "test.yml line 6 column 7 foo.list[1]: is not a number\n\
+            test.yml line 3 column 3 foo.qux: is required");
+    }
+
+    #[test]
+    fn test_id_string_keeps_digits_of_numbers() {
+        let doc = YamlNode::load("test.yml", "a: 012345678901\nb: \"123\"\nc: [1]\n").unwrap();
+        let mut errors = ConfigErrors::new("test.yml");
+        assert_eq!(doc.get("a").id_string(&mut errors), Some("012345678901".to_string()));
+        assert_eq!(doc.get("b").id_string(&mut errors), Some("123".to_string()));
+        assert_eq!(doc.get("c").id_string(&mut errors), None);
+        assert_eq!(error_msg(errors.result(())), "test.yml line 3 column 4 c: must be a string");
+    }
+
+    #[test]
+    fn test_mapping_reports_unknown_and_duplicate_keys() {
+        assert_eq!(error_msg(YamlNode::load("test.yml", "foo: 1\nfoo: 2\n")), "test.yml line 2 column 1 foo: duplicate key");
+        let doc = YamlNode::load("test.yml", "foo: 1\nbar: 2\n").unwrap();
+        let mut errors = ConfigErrors::new("test.yml");
+        assert!(doc.mapping(&["foo"], &mut errors));
+        assert_eq!(error_msg(errors.result(())), "test.yml line 2 column 1 bar: unknown key, expected one of foo");
+    }
+
+    #[test]
+    fn test_scalars() {
+        let doc = YamlNode::load("test.yml", "s: '1'\nb: true\ni: 3\nn: ~\n").unwrap();
+        let mut errors = ConfigErrors::new("test.yml");
+        assert_eq!(doc.get("s").string(&mut errors), Some("1".to_string()));
+        assert_eq!(doc.get("b").bool(&mut errors), Some(true));
+        assert_eq!(doc.get("i").positive_u32("days", &mut errors), Some(3));
+        assert!(doc.get("n").is_missing());
+        assert!(doc.get("x").is_missing());
+        assert!(errors.result(()).is_ok());
+        let mut errors = ConfigErrors::new("test.yml");
+        assert_eq!(doc.get("i").string(&mut errors), None);
+        assert_eq!(doc.get("s").positive_u32("days", &mut errors), None);
+        assert_eq!(error_msg(errors.result(())), "test.yml line 3 column 4 i: must be a string\n\
+            test.yml line 1 column 4 s: must be a positive number of days");
+    }
+
+    #[test]
+    fn test_to_json() {
+        let node = YamlNode::load("swoon.yml", "---\nplatforms:\n  mycloud:\n    region: north\n    size: 2\n    zones: [a, b]").unwrap();
+        assert_eq!(node.get("platforms").keys(), vec!("mycloud"));
+        assert_eq!(node.get("platforms").get("mycloud").to_json().to_string(),
+                   r#"{"region":"north","size":2,"zones":["a","b"]}"#);
+        assert!(node.get("missing").to_json().is_null());
+    }
+
+    #[test]
+    fn test_load_aliases() {
+        let doc = YamlNode::load("test.yml", "a: &x foo\nb: *x\n").unwrap();
+        assert_eq!(doc.get("b").string(&mut ConfigErrors::new("test.yml")), Some("foo".to_string()));
+    }
+
+    #[test]
+    fn test_load_invalid_yaml() {
+        assert!(error_msg(YamlNode::load("test.yml", "foo: [bar")).starts_with("test.yml is not valid yaml"));
+    }
+}
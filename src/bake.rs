@@ -1,14 +1,28 @@
 use dialoguer::Input;
 
-use crate::api::{command, task};
+use crate::api::{CloudPlatform, command, task};
 use crate::api::command::Name::Init;
 use crate::api::context::SwoonContext;
 use crate::api::util::DataDir;
-use crate::images::BakingPlan;
+use crate::images::{BakingPlan, InstanceImagePlan};
+use crate::images::archive::read_archived_images;
+use crate::images::inputs::PlanStatus;
+use crate::images::lifecycle::enforce_image_lifecycle;
+use crate::images::lock::SourceLock;
+use crate::images::provenance::GitState;
 use crate::packer::PackerBuild;
 
 pub struct BakeOpts {
     pub approve_plan: bool,
+    pub force: bool,
+    pub update_sources: bool,
+}
+
+/// An instance image plan for a single platform with its status compared to the last bake.
+struct InstancePlanEntry {
+    plan: InstanceImagePlan,
+    platform: CloudPlatform,
+    status: PlanStatus,
 }
 
 pub fn bake_machine_images(ctx: &SwoonContext, opts: &BakeOpts) -> command::Result {
@@ -18,27 +32,131 @@ pub fn bake_machine_images(ctx: &SwoonContext, opts: &BakeOpts) -> command::Resu
             vec!(Init),
         );
     }
+    if opts.update_sources && !ctx.config().pin_source_images {
+        return command::Error::result("--update-sources requires pin_source_images in swoon.yml");
+    }
     DataDir::init()?;
 
-    let baking_plan = BakingPlan::from(ctx)?;
-
-    if !opts.approve_plan {
-        if !prompt_for_approval(ctx, &baking_plan) {
-            ctx.write_line("Cancelling bake plan");
-            return command::SUCCESS;
+    let mut baking_plan = BakingPlan::from(ctx)?;
+    let source_lock = if ctx.config().pin_source_images {
+        let source_lock = SourceLock::resolve(ctx, &baking_plan.archetypes, opts.update_sources)?;
+        baking_plan.pin_source_images(&source_lock);
+        Some(source_lock)
+    } else {
+        None
+    };
+    let archived_images = read_archived_images()?;
+    let environment = ctx.environment().map(|environment| environment.name.as_str());
+    let archetype_build = PackerBuild::from_plan(ctx, &baking_plan)?;
+    let archetype_statuses = archetype_build.plan_statuses(ctx, &archived_images, environment)?;
+    let mut instance_entries = Vec::new();
+    for instance_plan in &baking_plan.instances {
+        for platform in &instance_plan.platforms {
+            let rebaking_archetype = archetype_build.specs().iter().zip(&archetype_statuses)
+                .any(|(spec, status)| &spec.platform == platform
+                    && spec.os() == &instance_plan.os
+                    && (opts.force || status != &PlanStatus::Unchanged));
+            let plan = instance_plan.with_platforms(vec!(platform.clone()));
+            let status = if rebaking_archetype {
+                PlanStatus::of(&archived_images, environment, platform, &instance_plan.name, &instance_plan.os, None)
+            } else {
+                PackerBuild::from_instance_plan(ctx, &plan)?.plan_statuses(ctx, &archived_images, environment)?.remove(0)
+            };
+            instance_entries.push(InstancePlanEntry {
+                plan,
+                platform: platform.clone(),
+                status,
+            });
         }
     }
 
-    PackerBuild::default_archetype(ctx).bake()?;
+    let selected = |status: &PlanStatus| opts.force || status != &PlanStatus::Unchanged;
+    let archetypes_selected: Vec<bool> = archetype_statuses.iter().map(selected).collect();
+    if !archetypes_selected.contains(&true) && !instance_entries.iter().any(|entry| selected(&entry.status)) {
+        ctx.write_line("All images are unchanged since their last bake. Use --force to rebake them.");
+        return command::SUCCESS;
+    }
 
+    // read before approval so that a failing git stops the bake before the plan is recorded
+    let git = GitState::read(ctx)?;
+    if !opts.approve_plan && !prompt_for_approval(ctx, opts, &archetype_build, &archetype_statuses, &instance_entries) {
+        ctx.write_line("Cancelling bake plan");
+        return command::SUCCESS;
+    }
+
+    baking_plan.version.record()?;
+    if let Some(source_lock) = source_lock {
+        source_lock.write()?;
+    }
+    let mut archetype_build = archetype_build.select(&archetypes_selected);
+    let mut baked = archetype_build.specs().to_vec();
+    if !archetype_build.is_empty() {
+        archetype_build.bake(ctx, &git)?;
+    }
+
+    for instance_plan in &baking_plan.instances {
+        let platforms: Vec<CloudPlatform> = instance_entries.iter()
+            .filter(|entry| entry.plan.name == instance_plan.name && selected(&entry.status))
+            .map(|entry| entry.platform.clone())
+            .collect();
+        if !platforms.is_empty() {
+            let mut instance_build = PackerBuild::from_instance_plan(ctx, &instance_plan.with_platforms(platforms))?;
+            instance_build.bake(ctx, &git)?;
+            baked.extend_from_slice(instance_build.specs());
+        }
+    }
+
+    enforce_image_lifecycle(ctx, &baked)?;
     command::SUCCESS
 }
 
-fn prompt_for_approval(ctx: &SwoonContext, baking_plan: &BakingPlan) -> bool {
-    let archetype_desc = format!("for {} on {}",
-                                 baking_plan.default_archetype.source_string(),
-                                 baking_plan.default_archetype.platform.to_str());
-    ctx.write_line(format!("Baking archetype images:\n    {}", archetype_desc).as_str());
+fn prompt_for_approval(ctx: &SwoonContext,
+                       opts: &BakeOpts,
+                       archetype_build: &PackerBuild,
+                       archetype_statuses: &[PlanStatus],
+                       instance_entries: &[InstancePlanEntry]) -> bool {
+    let skipping = |status: &PlanStatus| if !opts.force && status == &PlanStatus::Unchanged { ", skipping" } else { "" };
+    if let Some(environment) = ctx.environment() {
+        ctx.write_line(format!("Baking images for the {} environment", environment.name));
+    }
+    ctx.write_line("Baking archetype images:");
+    for (archetype, status) in archetype_build.specs().iter().zip(archetype_statuses) {
+        ctx.write_line(format!("    {} for {} on {} ({}{})",
+                               archetype.image_name(ctx.config()),
+                               archetype.source_string(),
+                               archetype.platform.to_str(),
+                               status,
+                               skipping(status)));
+    }
+    if !instance_entries.is_empty() {
+        ctx.write_line("Baking instance images:");
+        for entry in instance_entries {
+            ctx.write_line(format!("    {} archetype -> {} on {} ({}{})",
+                                   entry.plan.os,
+                                   entry.plan.name,
+                                   entry.platform.to_str(),
+                                   entry.status,
+                                   skipping(&entry.status)));
+        }
+    }
+    let retention = &ctx.config().image_retention;
+    if let Some(keep) = retention.keep {
+        ctx.write_line(format!("Deleting images beyond the newest {} of each baked image family", keep));
+    }
+    if let Some(state) = &retention.deprecate {
+        let mut platforms: Vec<&str> = Vec::new();
+        for spec in archetype_build.specs() {
+            let supports_deprecation = ctx.platforms.provider(&spec.platform)
+                .is_ok_and(|provider| provider.supports_deprecation());
+            if supports_deprecation && !platforms.contains(&spec.platform.to_str()) {
+                platforms.push(spec.platform.to_str());
+            }
+        }
+        if !platforms.is_empty() {
+            ctx.write_line(format!("Marking older images of each baked image family {} on {}",
+                                   state.to_str(), platforms.join(" and ")));
+        }
+    }
     let result = Input::<String>::new()
         .with_prompt("Type yes to bake images")
         .allow_empty(true)
@@ -0,0 +1,354 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use time::OffsetDateTime;
+use yaml_rust::{Yaml, YamlEmitter, YamlLoader};
+use yaml_rust::yaml::Hash;
+
+use crate::api::{CloudPlatform, OperatingSystem, task};
+use crate::api::output::file::Directory::{GeneratedRoot, ImageArchive};
+use crate::api::util::{copy_dir, format_timestamp, parse_timestamp};
+use crate::images::{ARCHETYPE_LABEL, ImageSpec};
+
+// .swoon/images/{platform}/archetypes/{os}/{image_name}/{files}
+// .swoon/images/gcp/archetypes/debian-11/eighty4-archetype-debian-11-20220104153012/{files}
+
+// .swoon/images/{platform}/instances/{instance_label}/{image_name}/{files}
+// .swoon/images/gcp/instances/postgres/eighty4-postgres-debian-11-20220104153012/{files}
+
+// images baked or promoted into an environment are archived in a dir for the environment
+// .swoon/images/{environment}/{platform}/archetypes/{os}/{image_name}/{files}
+
+const IMAGE_FILENAME: &str = "image.yml";
+const MANIFEST_FILENAME: &str = "packer-manifest.json";
+
+/// Record of a baked image kept in the image archive.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ArchivedImage {
+    pub image_name: String,
+    pub image_id: String,
+    pub inputs_hash: Option<String>,
+    pub label: String,
+    pub platform: CloudPlatform,
+    pub os: OperatingSystem,
+    /// Environment the image was baked or promoted into.
+    pub environment: Option<String>,
+    pub source_image: String,
+    /// Image that was copied into the environment by `swoon promote`.
+    pub promoted_from: Option<String>,
+    pub started_at: OffsetDateTime,
+    pub finished_at: OffsetDateTime,
+}
+
+impl ArchivedImage {
+    pub fn new(image_name: String,
+               image_id: String,
+               inputs_hash: Option<String>,
+               source_image: String,
+               spec: &ImageSpec,
+               started_at: OffsetDateTime,
+               finished_at: OffsetDateTime) -> Self {
+        Self {
+            image_name,
+            image_id,
+            inputs_hash,
+            label: spec.label.clone(),
+            platform: spec.platform.clone(),
+            os: spec.os().clone(),
+            environment: None,
+            source_image,
+            promoted_from: None,
+            started_at,
+            finished_at,
+        }
+    }
+
+    /// Path of the image's archive dir relative to the image archive.
+    pub fn archive_path(&self) -> PathBuf {
+        let platform_path = match &self.environment {
+            None => PathBuf::from(self.platform.to_str()),
+            Some(environment) => PathBuf::from(environment).join(self.platform.to_str()),
+        };
+        if self.label == ARCHETYPE_LABEL {
+            platform_path.join("archetypes").join(self.os.slug()).join(&self.image_name)
+        } else {
+            platform_path.join("instances").join(&self.label).join(&self.image_name)
+        }
+    }
+
+    pub fn duration_seconds(&self) -> i64 {
+        (self.finished_at - self.started_at).whole_seconds()
+    }
+
+    fn to_yaml(&self) -> task::Result<String> {
+        let mut hash = Hash::new();
+        let mut insert = |key: &str, value: String| {
+            hash.insert(Yaml::String(key.to_string()), Yaml::String(value));
+        };
+        insert("image_name", self.image_name.clone());
+        insert("image_id", self.image_id.clone());
+        if let Some(inputs_hash) = &self.inputs_hash {
+            insert("inputs_hash", inputs_hash.clone());
+        }
+        insert("label", self.label.clone());
+        insert("platform", self.platform.to_str().to_string());
+        insert("os", self.os.to_string());
+        if let Some(environment) = &self.environment {
+            insert("environment", environment.clone());
+        }
+        insert("source_image", self.source_image.clone());
+        if let Some(promoted_from) = &self.promoted_from {
+            insert("promoted_from", promoted_from.clone());
+        }
+        insert("started_at", format_timestamp(&self.started_at)?);
+        insert("finished_at", format_timestamp(&self.finished_at)?);
+        hash.insert(Yaml::String("duration_seconds".to_string()), Yaml::Integer(self.duration_seconds()));
+        let mut yaml = String::new();
+        if let Err(e) = YamlEmitter::new(&mut yaml).dump(&Yaml::Hash(hash)) {
+            return task::Error::result(format!("error writing {}: {:?}", IMAGE_FILENAME, e));
+        }
+        Ok(yaml)
+    }
+
+    fn parse(yaml_str: &str) -> task::Result<Self> {
+        let docs = match YamlLoader::load_from_str(yaml_str) {
+            Ok(docs) => docs,
+            Err(e) => return task::Error::result(format!("error reading {}: {}", IMAGE_FILENAME, e)),
+        };
+        let doc = match docs.first() {
+            Some(doc) => doc,
+            None => return task::Error::result(format!("{} is empty", IMAGE_FILENAME)),
+        };
+        let field = |key: &str| match doc[key].as_str() {
+            Some(s) => Ok(s.to_string()),
+            None => task::Error::result(format!("{} is missing {}", IMAGE_FILENAME, key)),
+        };
+        Ok(Self {
+            image_name: field("image_name")?,
+            image_id: field("image_id")?,
+            inputs_hash: doc["inputs_hash"].as_str().map(|s| s.to_string()),
+            label: field("label")?,
+            platform: CloudPlatform::parse(&field("platform")?)?,
+            os: OperatingSystem::from_string(&field("os")?)?,
+            environment: doc["environment"].as_str().map(|s| s.to_string()),
+            source_image: field("source_image")?,
+            promoted_from: doc["promoted_from"].as_str().map(|s| s.to_string()),
+            started_at: parse_timestamp(&field("started_at")?)?,
+            finished_at: parse_timestamp(&field("finished_at")?)?,
+        })
+    }
+}
+
+/// Files from the generated root that produced an image, copied into its archive dir.
+pub struct ArchivedFiles {
+    pub hcl_filename: String,
+    pub playbook_filename: Option<String>,
+    pub roles_dir: Option<PathBuf>,
+}
+
+/// Writes the image record and a snapshot of the files it was baked from to the image archive.
+pub fn archive_image(image: &ArchivedImage, files: &ArchivedFiles) -> task::Result<()> {
+    let dir = ImageArchive.create_sub_dir(image.archive_path().to_string_lossy())?;
+    fs::write(dir.join(IMAGE_FILENAME), image.to_yaml()?)?;
+    fs::copy(GeneratedRoot.join_path(MANIFEST_FILENAME), dir.join(MANIFEST_FILENAME))?;
+    fs::copy(GeneratedRoot.join_path(&files.hcl_filename), dir.join(&files.hcl_filename))?;
+    if let Some(playbook_filename) = &files.playbook_filename {
+        fs::copy(GeneratedRoot.join_path(playbook_filename), dir.join(playbook_filename))?;
+    }
+    if let Some(roles_dir) = &files.roles_dir {
+        copy_dir(roles_dir, &dir.join("roles"))?;
+    }
+    task::SUCCESS
+}
+
+/// Writes the record of a promoted image with a copy of the files its source image was baked from.
+pub fn archive_promoted_image(image: &ArchivedImage, source: &ArchivedImage) -> task::Result<()> {
+    let dir = ImageArchive.create_sub_dir(image.archive_path().to_string_lossy())?;
+    copy_dir(&ImageArchive.path().join(source.archive_path()), &dir)?;
+    fs::write(dir.join(IMAGE_FILENAME), image.to_yaml()?)?;
+    task::SUCCESS
+}
+
+/// Reads every image record in the image archive.
+pub fn read_archived_images() -> task::Result<Vec<ArchivedImage>> {
+    let mut images = Vec::new();
+    let archive_path = ImageArchive.path();
+    if archive_path.is_dir() {
+        find_archived_images(&archive_path, &mut images)?;
+    }
+    Ok(images)
+}
+
+fn find_archived_images(dir: &Path, images: &mut Vec<ArchivedImage>) -> task::Result<()> {
+    let image_path = dir.join(IMAGE_FILENAME);
+    if image_path.is_file() {
+        images.push(ArchivedImage::parse(&fs::read_to_string(image_path)?)?);
+        return task::SUCCESS;
+    }
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            find_archived_images(&path, images)?;
+        }
+    }
+    task::SUCCESS
+}
+
+/// Build written by packer's manifest post-processor.
+pub struct PackerManifestBuild {
+    pub name: String,
+    pub builder_type: String,
+    pub artifact_id: String,
+}
+
+/// Builds from the last packer run recorded in the generated root's packer-manifest.json.
+pub struct PackerManifest {
+    builds: Vec<PackerManifestBuild>,
+}
+
+impl PackerManifest {
+    /// Removes the manifest left by a previous packer run so the archive only copies the current run.
+    pub fn clear() -> task::Result<()> {
+        let manifest_path = GeneratedRoot.join_path(MANIFEST_FILENAME);
+        if manifest_path.is_file() {
+            fs::remove_file(manifest_path)?;
+        }
+        task::SUCCESS
+    }
+
+    pub fn read() -> task::Result<Self> {
+        let manifest_path = GeneratedRoot.join_path(MANIFEST_FILENAME);
+        if !manifest_path.is_file() {
+            return task::Error::result(format!("packer did not write {}", MANIFEST_FILENAME));
+        }
+        Self::parse(&fs::read_to_string(manifest_path)?)
+    }
+
+    /// Parses the manifest with the yaml parser, which also reads json.
+    fn parse(manifest_str: &str) -> task::Result<Self> {
+        let docs = match YamlLoader::load_from_str(manifest_str) {
+            Ok(docs) => docs,
+            Err(e) => return task::Error::result(format!("error reading {}: {}", MANIFEST_FILENAME, e)),
+        };
+        let manifest = match docs.first() {
+            Some(manifest) => manifest,
+            None => return task::Error::result(format!("{} is empty", MANIFEST_FILENAME)),
+        };
+        let last_run_uuid = manifest["last_run_uuid"].as_str().unwrap_or_default();
+        let builds = manifest["builds"].as_vec().map(|builds| builds.as_slice()).unwrap_or_default()
+            .iter()
+            .filter(|build| build["packer_run_uuid"].as_str() == Some(last_run_uuid))
+            .map(|build| PackerManifestBuild {
+                name: build["name"].as_str().unwrap_or_default().to_string(),
+                builder_type: build["builder_type"].as_str().unwrap_or_default().to_string(),
+                artifact_id: build["artifact_id"].as_str().unwrap_or_default().to_string(),
+            })
+            .collect();
+        Ok(Self { builds })
+    }
+
+    /// Finds the build of a packer source by its `source.{builder_type}.{name}` reference.
+    pub fn build(&self, source_name: &str) -> task::Result<&PackerManifestBuild> {
+        let mut parts = source_name.splitn(3, '.').skip(1);
+        let (builder_type, name) = (parts.next().unwrap_or_default(), parts.next().unwrap_or_default());
+        match self.builds.iter().find(|build| build.builder_type == builder_type && build.name == name) {
+            Some(build) => Ok(build),
+            None => task::Error::result(format!("{} has no build for {}", MANIFEST_FILENAME, source_name)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use crate::api::DEBIAN_11;
+
+    use super::*;
+
+    fn archived_image(label: &str) -> ArchivedImage {
+        ArchivedImage {
+            image_name: format!("eighty4-{}-debian-11-20220104153012", label),
+            image_id: "1234567890".to_string(),
+            inputs_hash: Some("abc".to_string()),
+            environment: None,
+            promoted_from: None,
+            label: label.to_string(),
+            platform: CloudPlatform::GCP,
+            os: DEBIAN_11,
+            source_image: "debian:11".to_string(),
+            started_at: datetime!(2022-01-04 15:30:12 UTC),
+            finished_at: datetime!(2022-01-04 15:42:30 UTC),
+        }
+    }
+
+    #[test]
+    fn test_archived_image_archive_path() {
+        assert_eq!(archived_image("archetype").archive_path(),
+                   PathBuf::from("gcp/archetypes/debian-11/eighty4-archetype-debian-11-20220104153012"));
+        assert_eq!(archived_image("postgres").archive_path(),
+                   PathBuf::from("gcp/instances/postgres/eighty4-postgres-debian-11-20220104153012"));
+        let image = ArchivedImage {
+            environment: Some("prod".to_string()),
+            ..archived_image("archetype")
+        };
+        assert_eq!(image.archive_path(),
+                   PathBuf::from("prod/gcp/archetypes/debian-11/eighty4-archetype-debian-11-20220104153012"));
+    }
+
+    #[test]
+    fn test_archived_image_to_yaml() {
+        let yaml = archived_image("archetype").to_yaml().unwrap();
+        assert!(yaml.contains("image_id: \"1234567890\""));
+        assert!(yaml.contains("os: \"debian:11\""));
+        assert!(yaml.contains("started_at: \"2022-01-04T15:30:12Z\""));
+        assert!(yaml.contains("duration_seconds: 738"));
+    }
+
+    #[test]
+    fn test_archived_image_parse() {
+        let image = archived_image("postgres");
+        assert_eq!(ArchivedImage::parse(&image.to_yaml().unwrap()).unwrap(), image);
+        let promoted = ArchivedImage {
+            environment: Some("prod".to_string()),
+            promoted_from: Some("eighty4-postgres-debian-11-dev-20220104153012".to_string()),
+            ..image
+        };
+        assert_eq!(ArchivedImage::parse(&promoted.to_yaml().unwrap()).unwrap(), promoted);
+    }
+
+    #[test]
+    fn test_packer_manifest_finds_builds_from_last_run() {
+        let manifest = PackerManifest::parse(r#"{
+          "builds": [
+            {
+              "name": "archetype-debian-11",
+              "builder_type": "googlecompute",
+              "build_time": 1641310000,
+              "artifact_id": "eighty4-archetype-debian-11-1",
+              "packer_run_uuid": "previous-run"
+            },
+            {
+              "name": "archetype-debian-11",
+              "builder_type": "googlecompute",
+              "build_time": 1641311000,
+              "artifact_id": "eighty4-archetype-debian-11-2",
+              "packer_run_uuid": "last-run"
+            },
+            {
+              "name": "archetype-debian-11",
+              "builder_type": "amazon-ebs",
+              "build_time": 1641311000,
+              "artifact_id": "us-east-1:ami-0123456789",
+              "packer_run_uuid": "last-run"
+            }
+          ],
+          "last_run_uuid": "last-run"
+        }"#).unwrap();
+        let gcp = manifest.build("source.googlecompute.archetype-debian-11").unwrap();
+        assert_eq!(gcp.artifact_id, "eighty4-archetype-debian-11-2");
+        let aws = manifest.build("source.amazon-ebs.archetype-debian-11").unwrap();
+        assert_eq!(aws.artifact_id, "us-east-1:ami-0123456789");
+        assert!(manifest.build("source.azure-arm.archetype-debian-11").is_err());
+    }
+}
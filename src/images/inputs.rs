@@ -0,0 +1,216 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use sha2::{Digest, Sha256};
+
+use crate::ansible::AnsiblePlaybook;
+use crate::api::{CloudPlatform, OperatingSystem, task};
+use crate::api::output::file::Directory::{AnsibleRoles, ProjectRoot};
+use crate::images::archive::ArchivedImage;
+use crate::platforms::images::PlatformImage;
+
+/// Whether an image in the baking plan differs from the last archived or labeled bake of the same image.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PlanStatus {
+    New,
+    Changed,
+    Unchanged,
+}
+
+impl PlanStatus {
+    /// Compares an inputs hash with the hash recorded by the latest archived bake of the image in
+    /// the environment. Images without an inputs hash, like instances of a rebaked archetype, are
+    /// never unchanged.
+    pub fn of(archived_images: &[ArchivedImage],
+              environment: Option<&str>,
+              platform: &CloudPlatform,
+              label: &str,
+              os: &OperatingSystem,
+              inputs_hash: Option<&str>) -> Self {
+        let last_bake = archived_images.iter()
+            .filter(|archived| archived.environment.as_deref() == environment)
+            .filter(|archived| &archived.platform == platform && archived.label == label && &archived.os == os)
+            .max_by_key(|archived| archived.finished_at);
+        match last_bake {
+            None => PlanStatus::New,
+            Some(archived) if inputs_hash.is_some() && archived.inputs_hash.as_deref() == inputs_hash => PlanStatus::Unchanged,
+            Some(_) => PlanStatus::Changed,
+        }
+    }
+
+    /// Falls back to the inputs hash label of the newest image of the family on the platform when
+    /// the archive can't tell that an image is unchanged, like on a fresh checkout or CI runner.
+    /// Labels holding a shortened inputs hash match by prefix.
+    pub fn or_labeled(self, family_images: &[PlatformImage], inputs_hash: Option<&str>) -> Self {
+        if self == PlanStatus::Unchanged {
+            return self;
+        }
+        let newest = match family_images.iter().max_by_key(|image| image.created_at) {
+            None => return self,
+            Some(image) => image,
+        };
+        match (newest.inputs_hash.as_deref(), inputs_hash) {
+            (Some(labeled), Some(inputs_hash)) if inputs_hash.starts_with(labeled) => PlanStatus::Unchanged,
+            _ => PlanStatus::Changed,
+        }
+    }
+}
+
+impl fmt::Display for PlanStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", match self {
+            PlanStatus::New => "new",
+            PlanStatus::Changed => "changed",
+            PlanStatus::Unchanged => "unchanged",
+        })
+    }
+}
+
+/// Sha256 of everything that goes into baking an image: the rendered packer HCL, the playbook,
+/// the ansible role files, the source image and the version of swoon doing the baking.
+pub fn inputs_hash(hcl: &str, source_image: &str, playbook: Option<&AnsiblePlaybook>) -> task::Result<String> {
+    let mut inputs_hash = InputsHash {
+        hasher: Sha256::new(),
+    };
+    inputs_hash.update("swoon", env!("CARGO_PKG_VERSION").as_bytes());
+    inputs_hash.update("hcl", hcl.as_bytes());
+    inputs_hash.update("source_image", source_image.as_bytes());
+    inputs_hash.playbook(playbook)?;
+    Ok(inputs_hash.finish())
+}
+
+struct InputsHash {
+    hasher: Sha256,
+}
+
+impl InputsHash {
+    fn playbook(&mut self, playbook_opt: Option<&AnsiblePlaybook>) -> task::Result<()> {
+        if let Some(playbook) = playbook_opt {
+            let playbook_path = ProjectRoot.join_path(&playbook.path);
+            if !playbook_path.is_file() {
+                return task::Error::result(format!("{} playbook not found in project dir", playbook.path));
+            }
+            self.update("playbook", &fs::read(playbook_path)?);
+            let roles_path = AnsibleRoles.path();
+            if roles_path.is_dir() {
+                self.roles_dir(&roles_path, &roles_path)?;
+            }
+        }
+        task::SUCCESS
+    }
+
+    fn roles_dir(&mut self, roles_path: &Path, dir: &Path) -> task::Result<()> {
+        let mut paths = fs::read_dir(dir)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        paths.sort();
+        for path in paths {
+            if path.is_dir() {
+                self.roles_dir(roles_path, &path)?;
+            } else {
+                let relative_path = path.strip_prefix(roles_path).unwrap_or(&path);
+                self.update("role_file", relative_path.to_string_lossy().as_bytes());
+                self.update("role_file_content", &fs::read(&path)?);
+            }
+        }
+        task::SUCCESS
+    }
+
+    /// Hashes each input with its name and length so that inputs can't run into each other.
+    fn update(&mut self, name: &str, bytes: &[u8]) {
+        self.hasher.update(name.as_bytes());
+        self.hasher.update(bytes.len().to_le_bytes());
+        self.hasher.update(bytes);
+    }
+
+    fn finish(self) -> String {
+        self.hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use crate::api::{DEBIAN_11, UBUNTU_2004};
+
+    use super::*;
+
+    fn archived_image(inputs_hash: Option<&str>) -> ArchivedImage {
+        ArchivedImage {
+            image_name: "eighty4-archetype-debian-11-1".to_string(),
+            image_id: "1234567890".to_string(),
+            inputs_hash: inputs_hash.map(|s| s.to_string()),
+            environment: None,
+            promoted_from: None,
+            label: "archetype".to_string(),
+            platform: CloudPlatform::GCP,
+            os: DEBIAN_11,
+            source_image: "debian:11".to_string(),
+            started_at: datetime!(2022-01-04 15:30:12 UTC),
+            finished_at: datetime!(2022-01-04 15:42:30 UTC),
+        }
+    }
+
+    #[test]
+    fn test_plan_status_of() {
+        let archived_images = vec!(archived_image(Some("abc")));
+        let status = |os, inputs_hash| PlanStatus::of(&archived_images, None, &CloudPlatform::GCP, "archetype", os, inputs_hash);
+        assert_eq!(status(&DEBIAN_11, Some("abc")), PlanStatus::Unchanged);
+        assert_eq!(status(&DEBIAN_11, Some("def")), PlanStatus::Changed);
+        assert_eq!(status(&DEBIAN_11, None), PlanStatus::Changed);
+        assert_eq!(status(&UBUNTU_2004, Some("abc")), PlanStatus::New);
+    }
+
+    #[test]
+    fn test_plan_status_of_environment() {
+        let archived_images = vec!(ArchivedImage {
+            environment: Some("dev".to_string()),
+            ..archived_image(Some("abc"))
+        });
+        let status = |environment| PlanStatus::of(&archived_images, environment, &CloudPlatform::GCP, "archetype", &DEBIAN_11, Some("abc"));
+        assert_eq!(status(Some("dev")), PlanStatus::Unchanged);
+        assert_eq!(status(Some("prod")), PlanStatus::New);
+        assert_eq!(status(None), PlanStatus::New);
+    }
+
+    #[test]
+    fn test_plan_status_changed_without_archived_hash() {
+        let archived_images = vec!(archived_image(None));
+        let status = PlanStatus::of(&archived_images, None, &CloudPlatform::GCP, "archetype", &DEBIAN_11, Some("abc"));
+        assert_eq!(status, PlanStatus::Changed);
+    }
+
+    #[test]
+    fn test_plan_status_or_labeled() {
+        let family_image = |name: &str, created_at, inputs_hash: Option<&str>| PlatformImage {
+            name: name.to_string(),
+            id: name.to_string(),
+            created_at,
+            status: "ready".to_string(),
+            inputs_hash: inputs_hash.map(|s| s.to_string()),
+        };
+        let family_images = vec!(
+            family_image("eighty4-archetype-debian-11-2", datetime!(2022-01-05 12:00 UTC), Some("abcd")),
+            family_image("eighty4-archetype-debian-11-1", datetime!(2022-01-04 12:00 UTC), Some("def0")),
+        );
+        assert_eq!(PlanStatus::New.or_labeled(&family_images, Some("abcd")), PlanStatus::Unchanged);
+        assert_eq!(PlanStatus::Changed.or_labeled(&family_images, Some("abcdef")), PlanStatus::Unchanged);
+        assert_eq!(PlanStatus::New.or_labeled(&family_images, Some("def0")), PlanStatus::Changed);
+        assert_eq!(PlanStatus::New.or_labeled(&family_images, None), PlanStatus::Changed);
+        assert_eq!(PlanStatus::New.or_labeled(&[], Some("abcd")), PlanStatus::New);
+        assert_eq!(PlanStatus::Unchanged.or_labeled(&[], Some("abcd")), PlanStatus::Unchanged);
+        let unlabeled = vec!(family_image("eighty4-archetype-debian-11-1", datetime!(2022-01-04 12:00 UTC), None));
+        assert_eq!(PlanStatus::New.or_labeled(&unlabeled, Some("abcd")), PlanStatus::Changed);
+    }
+
+    #[test]
+    fn test_inputs_hash() {
+        let hash = |hcl: &str, source_image: &str| inputs_hash(hcl, source_image, None).unwrap();
+        assert_eq!(hash("source {}", "debian:11"), hash("source {}", "debian:11"));
+        assert_ne!(hash("source {}", "debian:11"), hash("source {}", "debian:10"));
+        assert_ne!(hash("source {}debian", ":11"), hash("source {}", "debian:11"));
+        assert_eq!(hash("", "").len(), 64);
+    }
+}
@@ -0,0 +1,173 @@
+use std::cmp::Reverse;
+
+use crate::api::task;
+use crate::api::config::DeprecationState;
+use crate::images::{image_name_prefix, ImageSpec};
+use crate::platforms::images::{self as platform_images, PlatformImage};
+use crate::SwoonContext;
+
+/// Older images of an image family that the image retention policy deprecates or deletes
+/// once a newer image of the family has been baked.
+struct FamilyLifecycle<'a> {
+    deprecate: Vec<&'a PlatformImage>,
+    delete: Vec<&'a PlatformImage>,
+}
+
+impl<'a> FamilyLifecycle<'a> {
+    /// Keeps the newest images of a family up to the retention count, deleting the rest and
+    /// marking kept images other than the newest with the policy's deprecation state.
+    fn plan(keep: Option<u32>,
+            deprecation: Option<&DeprecationState>,
+            newest: &str,
+            images: &'a [PlatformImage]) -> Self {
+        let mut older: Vec<&PlatformImage> = images.iter().filter(|image| image.name != newest).collect();
+        older.sort_by_key(|image| Reverse(image.created_at));
+        let kept = keep.map_or(older.len(), |keep| older.len().min(keep as usize - 1));
+        let delete = older.split_off(kept);
+        let deprecate = match deprecation {
+            None => Vec::new(),
+            Some(state) => older.into_iter()
+                .filter(|image| image.status.to_uppercase() != state.to_str())
+                .collect(),
+        };
+        Self {
+            deprecate,
+            delete,
+        }
+    }
+}
+
+/// Enforces the image retention policy on the families of freshly baked images,
+/// replacing older images with the baked image. Images are only deprecated on platforms that support it.
+pub fn enforce_image_lifecycle(ctx: &SwoonContext, baked: &[ImageSpec]) -> task::Result<()> {
+    let cfg = ctx.config();
+    if !cfg.image_retention.has_lifecycle() {
+        return task::SUCCESS;
+    }
+    for spec in baked {
+        let prefix = image_name_prefix(cfg, &spec.label, spec.os());
+        let images: Vec<PlatformImage> = match platform_images::images_by_prefix(ctx, &spec.platform, &prefix)? {
+            None => continue,
+            Some(images) => images.into_iter().filter(|image| in_family(&prefix, &image.name)).collect(),
+        };
+        let newest = spec.image_name(cfg);
+        let supports_deprecation = ctx.platforms.provider(&spec.platform)?.supports_deprecation();
+        let deprecation = cfg.image_retention.deprecate.as_ref().filter(|_| supports_deprecation);
+        let lifecycle = FamilyLifecycle::plan(cfg.image_retention.keep, deprecation, &newest, &images);
+        if let Some(state) = deprecation {
+            for image in lifecycle.deprecate {
+                ctx.write_line(format!("Marking {} on {} {} with replacement {}",
+                                       image.name, spec.platform.to_str(), state.to_str(), newest));
+                platform_images::deprecate_image(ctx, &spec.platform, image, state, &newest)?;
+            }
+        }
+        for image in lifecycle.delete {
+            ctx.write_line(format!("Deleting {} on {}", image.name, spec.platform.to_str()));
+            platform_images::delete_image(ctx, &spec.platform, image)?;
+        }
+    }
+    task::SUCCESS
+}
+
+/// Whether an image was named by a bake of the family's prefix. Image versions start with a
+/// timestamp or build number, so images of an environment sharing the prefix are excluded.
+pub fn in_family(prefix: &str, image_name: &str) -> bool {
+    image_name.strip_prefix(prefix)
+        .and_then(|version| version.chars().next())
+        .is_some_and(|c| c.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    use tempdir::TempDir;
+    use time::macros::datetime;
+    use time::OffsetDateTime;
+
+    use crate::platforms::aws::AwsContext;
+    use crate::platforms::aws::provider::AwsProvider;
+    use crate::platforms::provider::PlatformProvider;
+
+    use super::*;
+
+    fn image(name: &str, created_at: OffsetDateTime, status: &str) -> PlatformImage {
+        PlatformImage {
+            name: name.to_string(),
+            id: name.to_string(),
+            created_at,
+            status: status.to_string(),
+            inputs_hash: None,
+        }
+    }
+
+    fn images() -> Vec<PlatformImage> {
+        vec!(
+            image("eighty4-archetype-debian-11-1", datetime!(2022-01-01 12:00 UTC), "deprecated"),
+            image("eighty4-archetype-debian-11-3", datetime!(2022-01-03 12:00 UTC), "ready"),
+            image("eighty4-archetype-debian-11-2", datetime!(2022-01-02 12:00 UTC), "ready"),
+            image("eighty4-archetype-debian-11-4", datetime!(2022-01-04 12:00 UTC), "ready"),
+        )
+    }
+
+    fn names(images: &[&PlatformImage]) -> Vec<String> {
+        images.iter().map(|image| image.name.clone()).collect()
+    }
+
+    #[test]
+    fn test_lifecycle_plan_keeps_newest_images() {
+        let images = images();
+        let lifecycle = FamilyLifecycle::plan(Some(2), None, "eighty4-archetype-debian-11-4", &images);
+        assert!(lifecycle.deprecate.is_empty());
+        assert_eq!(names(&lifecycle.delete), vec!("eighty4-archetype-debian-11-2", "eighty4-archetype-debian-11-1"));
+    }
+
+    #[test]
+    fn test_lifecycle_plan_deprecates_kept_images() {
+        let images = images();
+        let lifecycle = FamilyLifecycle::plan(Some(3), Some(&DeprecationState::Deprecated), "eighty4-archetype-debian-11-4", &images);
+        assert_eq!(names(&lifecycle.deprecate), vec!("eighty4-archetype-debian-11-3", "eighty4-archetype-debian-11-2"));
+        assert_eq!(names(&lifecycle.delete), vec!("eighty4-archetype-debian-11-1"));
+    }
+
+    #[test]
+    fn test_lifecycle_plan_skips_images_in_deprecation_state() {
+        let images = images();
+        let lifecycle = FamilyLifecycle::plan(None, Some(&DeprecationState::Deprecated), "eighty4-archetype-debian-11-4", &images);
+        assert_eq!(names(&lifecycle.deprecate), vec!("eighty4-archetype-debian-11-3", "eighty4-archetype-debian-11-2"));
+        assert!(lifecycle.delete.is_empty());
+    }
+
+    #[test]
+    fn test_lifecycle_deletes_aws_snapshots() {
+        let work_dir = TempDir::new("swoon_lifecycle_test").unwrap();
+        let calls_path = work_dir.path().join("calls");
+        let aws_path = work_dir.path().join("aws");
+        fs::write(&aws_path, format!("#!/bin/sh\necho \"$@\" >> {}\n[ \"$2\" = describe-images ] && printf 'snap-a\\tsnap-b\\n'\nexit 0\n",
+                                     calls_path.display())).unwrap();
+        fs::set_permissions(&aws_path, fs::Permissions::from_mode(0o755)).unwrap();
+        let provider = AwsProvider::new(AwsContext {
+            account_id: "123456789012".to_string(),
+            region: "us-east-1".to_string(),
+        }, aws_path);
+        let images = images();
+        let lifecycle = FamilyLifecycle::plan(Some(3), None, "eighty4-archetype-debian-11-4", &images);
+        for image in lifecycle.delete {
+            provider.delete_image(image).unwrap();
+        }
+        assert_eq!(fs::read_to_string(&calls_path).unwrap().lines().collect::<Vec<&str>>(), vec!(
+            "ec2 describe-images --region=us-east-1 --image-ids=eighty4-archetype-debian-11-1 --query=Images[].BlockDeviceMappings[].Ebs.SnapshotId --output=text --no-cli-pager",
+            "ec2 deregister-image --region=us-east-1 --image-id=eighty4-archetype-debian-11-1 --no-cli-pager",
+            "ec2 delete-snapshot --region=us-east-1 --snapshot-id=snap-a --no-cli-pager",
+            "ec2 delete-snapshot --region=us-east-1 --snapshot-id=snap-b --no-cli-pager",
+        ));
+    }
+
+    #[test]
+    fn test_in_family() {
+        assert!(in_family("eighty4-archetype-debian-11-", "eighty4-archetype-debian-11-20220104153012"));
+        assert!(!in_family("eighty4-archetype-debian-11-", "eighty4-archetype-debian-11-stg-20220104153012"));
+        assert!(!in_family("eighty4-archetype-debian-11-", "eighty4-postgres-debian-11-1"));
+    }
+}
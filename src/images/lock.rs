@@ -0,0 +1,212 @@
+use std::fs;
+
+use yaml_rust::{Yaml, YamlEmitter, YamlLoader};
+use yaml_rust::yaml::Hash;
+
+use crate::api::{CloudPlatform, OperatingSystem, task};
+use crate::api::output::file::Directory::ProjectRoot;
+use crate::images::{ImageSource, ImageSpec};
+use crate::platforms::images as platform_images;
+use crate::SwoonContext;
+
+pub const LOCK_FILENAME: &str = "swoon.lock";
+
+/// Exact OS images that archetype images are baked from while `pin_source_images` is enabled.
+/// Locked images are reused by every bake until `swoon bake --update-sources` resolves them again.
+/// Images are locked per environment, since an environment's region or project can resolve to
+/// a different image, such as the regional AMIs of AWS.
+#[derive(Debug, PartialEq)]
+pub struct SourceLock {
+    /// Environment that archetypes are pinned for.
+    environment: Option<String>,
+    source_images: Vec<LockedSourceImage>,
+}
+
+#[derive(Debug, PartialEq)]
+struct LockedSourceImage {
+    environment: Option<String>,
+    platform: CloudPlatform,
+    os: OperatingSystem,
+    image: String,
+}
+
+impl SourceLock {
+    /// Locks the source image of each archetype, resolving the current image of its OS for
+    /// archetypes missing from swoon.lock or for all archetypes when updating sources. Images locked
+    /// for other environments are kept as they are.
+    pub fn resolve(ctx: &SwoonContext, archetypes: &[ImageSpec], update_sources: bool) -> task::Result<Self> {
+        let environment = ctx.environment().map(|environment| environment.name.clone());
+        let locked = Self::read()?;
+        let mut resolved = Vec::new();
+        for spec in archetypes {
+            let image = match locked.image(&environment, &spec.platform, spec.os()).filter(|_| !update_sources) {
+                Some(image) => Some(image.to_string()),
+                None => platform_images::current_os_image(ctx, &spec.platform, spec.os())?,
+            };
+            if let Some(image) = image {
+                resolved.push(LockedSourceImage {
+                    environment: environment.clone(),
+                    platform: spec.platform.clone(),
+                    os: spec.os().clone(),
+                    image,
+                });
+            }
+        }
+        let mut source_images: Vec<LockedSourceImage> = locked.source_images.into_iter()
+            .filter(|locked| locked.environment != environment)
+            .collect();
+        source_images.extend(resolved);
+        Ok(Self {
+            environment,
+            source_images,
+        })
+    }
+
+    fn empty() -> Self {
+        Self {
+            environment: None,
+            source_images: Vec::new(),
+        }
+    }
+
+    fn read() -> task::Result<Self> {
+        let path = ProjectRoot.join_path(LOCK_FILENAME);
+        if path.is_file() {
+            Self::parse(&fs::read_to_string(path)?)
+        } else {
+            Ok(Self::empty())
+        }
+    }
+
+    pub fn write(&self) -> task::Result<()> {
+        ProjectRoot.write(None, LOCK_FILENAME, self.to_yaml()?.as_str())
+    }
+
+    fn image(&self, environment: &Option<String>, platform: &CloudPlatform, os: &OperatingSystem) -> Option<&str> {
+        self.source_images.iter()
+            .find(|locked| &locked.environment == environment && &locked.platform == platform && &locked.os == os)
+            .map(|locked| locked.image.as_str())
+    }
+
+    /// Builds an archetype from its locked image instead of its OS image family.
+    pub fn pin(&self, spec: &ImageSpec) -> ImageSpec {
+        match (&spec.source, self.image(&self.environment, &spec.platform, spec.os())) {
+            (ImageSource::OperatingSystem { os }, Some(image)) => ImageSpec {
+                source: ImageSource::Image { name: image.to_string(), os: os.clone() },
+                ..spec.clone()
+            },
+            _ => spec.clone(),
+        }
+    }
+
+    fn to_yaml(&self) -> task::Result<String> {
+        let source_images = self.source_images.iter()
+            .map(|locked| {
+                let mut hash = Hash::new();
+                let mut insert = |key: &str, value: String| {
+                    hash.insert(Yaml::String(key.to_string()), Yaml::String(value));
+                };
+                if let Some(environment) = &locked.environment {
+                    insert("environment", environment.clone());
+                }
+                insert("platform", locked.platform.to_str().to_string());
+                insert("os", locked.os.to_string());
+                insert("image", locked.image.clone());
+                Yaml::Hash(hash)
+            })
+            .collect();
+        let mut hash = Hash::new();
+        hash.insert(Yaml::String("source_images".to_string()), Yaml::Array(source_images));
+        let mut yaml = String::new();
+        if let Err(e) = YamlEmitter::new(&mut yaml).dump(&Yaml::Hash(hash)) {
+            return task::Error::result(format!("error writing {}: {:?}", LOCK_FILENAME, e));
+        }
+        yaml.push('\n');
+        Ok(yaml)
+    }
+
+    fn parse(yaml_str: &str) -> task::Result<Self> {
+        let docs = match YamlLoader::load_from_str(yaml_str) {
+            Ok(docs) => docs,
+            Err(e) => return task::Error::result(format!("error reading {}: {}", LOCK_FILENAME, e)),
+        };
+        let source_images = match docs.first().and_then(|doc| doc["source_images"].as_vec()) {
+            None => return Ok(Self::empty()),
+            Some(source_images) => source_images,
+        };
+        let source_images = source_images.iter()
+            .map(|locked| {
+                let field = |key: &str| match locked[key].as_str() {
+                    Some(s) => Ok(s.to_string()),
+                    None => task::Error::result(format!("{} source image is missing {}", LOCK_FILENAME, key)),
+                };
+                Ok(LockedSourceImage {
+                    environment: locked["environment"].as_str().map(|environment| environment.to_string()),
+                    platform: CloudPlatform::parse(&field("platform")?)?,
+                    os: OperatingSystem::from_string(&field("os")?)?,
+                    image: field("image")?,
+                })
+            })
+            .collect::<task::Result<Vec<LockedSourceImage>>>()?;
+        Ok(Self {
+            environment: None,
+            source_images,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::{DEBIAN_11, UBUNTU_2004};
+
+    use super::*;
+
+    fn source_lock() -> SourceLock {
+        SourceLock {
+            environment: None,
+            source_images: vec!(
+                LockedSourceImage {
+                    environment: None,
+                    platform: CloudPlatform::GCP,
+                    os: DEBIAN_11,
+                    image: "debian-11-bullseye-v20211209".to_string(),
+                },
+                LockedSourceImage {
+                    environment: Some("prod".to_string()),
+                    platform: CloudPlatform::GCP,
+                    os: DEBIAN_11,
+                    image: "debian-11-bullseye-v20220301".to_string(),
+                },
+            ),
+        }
+    }
+
+    #[test]
+    fn test_source_lock_round_trip() {
+        let source_lock = source_lock();
+        let yaml = source_lock.to_yaml().unwrap();
+        assert!(yaml.contains("image: debian-11-bullseye-v20211209"));
+        assert!(yaml.contains("environment: prod"));
+        assert_eq!(SourceLock::parse(&yaml).unwrap(), source_lock);
+    }
+
+    #[test]
+    fn test_source_lock_pin() {
+        let source_lock = source_lock();
+        let spec = |os: &OperatingSystem| ImageSpec {
+            label: "archetype".to_string(),
+            platform: CloudPlatform::GCP,
+            source: ImageSource::OperatingSystem { os: os.clone() },
+            version: "1".to_string(),
+        };
+        assert_eq!(source_lock.pin(&spec(&DEBIAN_11)).source_string(), "debian-11-bullseye-v20211209");
+        assert_eq!(source_lock.pin(&spec(&UBUNTU_2004)).source_string(), "ubuntu:20.04");
+        let prod_lock = SourceLock { environment: Some("prod".to_string()), ..source_lock };
+        assert_eq!(prod_lock.pin(&spec(&DEBIAN_11)).source_string(), "debian-11-bullseye-v20220301");
+    }
+
+    #[test]
+    fn test_parse_source_lock_without_source_images() {
+        assert_eq!(SourceLock::parse("---\n").unwrap(), SourceLock::empty());
+    }
+}
@@ -1,44 +1,312 @@
+use std::fs;
+
+use time::macros::format_description;
+use time::OffsetDateTime;
+
 use crate::api::{CloudPlatform, OperatingSystem, task};
+use crate::api::binaries::PathLookup;
+use crate::api::config::{ImageVersionScheme, SwoonConfig};
+use crate::api::output::file::Directory::GeneratedRoot;
+use crate::api::process::Process;
+use crate::images::lock::SourceLock;
 use crate::SwoonContext;
 
+pub mod archive;
+pub mod inputs;
+pub mod lifecycle;
+pub mod lock;
+pub mod provenance;
+
+pub const ARCHETYPE_LABEL: &str = "archetype";
+
+#[derive(Clone)]
 pub enum ImageSource {
     OperatingSystem { os: OperatingSystem },
-    Image { name: String },
+    Image { name: String, os: OperatingSystem },
+    /// Image family of the org's images, resolving to its newest image.
+    Family { name: String, os: OperatingSystem },
 }
 
+#[derive(Clone)]
 pub struct ImageSpec {
+    pub label: String,
     pub platform: CloudPlatform,
     pub source: ImageSource,
+    pub version: String,
 }
 
 impl ImageSpec {
+    pub fn archetype(platform: &CloudPlatform, os: &OperatingSystem, version: &ImageVersion) -> Self {
+        Self {
+            label: ARCHETYPE_LABEL.to_string(),
+            platform: platform.clone(),
+            source: ImageSource::OperatingSystem { os: os.clone() },
+            version: version.value.clone(),
+        }
+    }
+
+    pub fn os(&self) -> &OperatingSystem {
+        match &self.source {
+            ImageSource::OperatingSystem { os } => os,
+            ImageSource::Image { os, .. } => os,
+            ImageSource::Family { os, .. } => os,
+        }
+    }
+
+    pub fn image_name(&self, cfg: &SwoonConfig) -> String {
+        format!("{}{}", image_name_prefix(cfg, &self.label, self.os()), self.version)
+    }
+
+    /// Image family shared by every bake of this image, resolving to its newest image.
+    pub fn image_family(&self, cfg: &SwoonConfig) -> String {
+        image_family(cfg, &self.label, self.os())
+    }
+
+    pub fn image_description(&self, cfg: &SwoonConfig) -> String {
+        format!("{} {} image for {} baked by swoon", cfg.org_name, self.label, self.os())
+    }
+
+    pub fn source_label(&self) -> String {
+        format!("{}-{}", self.label, self.os().slug())
+    }
+
     pub fn source_string(&self) -> String {
         match &self.source {
             ImageSource::OperatingSystem { os } => os.to_string(),
-            ImageSource::Image { name } => name.clone(),
+            ImageSource::Image { name, .. } => name.clone(),
+            ImageSource::Family { name, .. } => name.clone(),
+        }
+    }
+}
+
+/// An instance image built on top of the latest archetype image baked for its platform and OS.
+#[derive(Clone)]
+pub struct InstanceImagePlan {
+    pub name: String,
+    pub os: OperatingSystem,
+    pub platforms: Vec<CloudPlatform>,
+    pub playbook: String,
+    pub version: String,
+}
+
+impl InstanceImagePlan {
+    pub fn archetype_name_prefix(&self, cfg: &SwoonConfig) -> String {
+        image_name_prefix(cfg, ARCHETYPE_LABEL, &self.os)
+    }
+
+    pub fn archetype_family(&self, cfg: &SwoonConfig) -> String {
+        image_family(cfg, ARCHETYPE_LABEL, &self.os)
+    }
+
+    pub fn with_platforms(&self, platforms: Vec<CloudPlatform>) -> Self {
+        Self {
+            platforms,
+            ..self.clone()
+        }
+    }
+}
+
+/// Suffix shared by the names of every image baked by a single bake.
+pub struct ImageVersion {
+    pub value: String,
+    build_number: Option<u32>,
+}
+
+impl ImageVersion {
+    const BUILD_NUMBER_FILENAME: &'static str = "build_number";
+
+    pub fn next(ctx: &SwoonContext) -> task::Result<Self> {
+        let cfg = ctx.config();
+        let (mut value, build_number) = match cfg.image_naming.version {
+            ImageVersionScheme::BuildNumber => {
+                let build_number = Self::last_build_number()? + 1;
+                (build_number.to_string(), Some(build_number))
+            }
+            ImageVersionScheme::Timestamp => (Self::utc_timestamp()?, None),
+        };
+        if cfg.image_naming.git_sha {
+            let git_sha = Process::invoke(&ctx.git_path()?, ["rev-parse", "--short", "HEAD"])?;
+            value = format!("{}-{}", value, git_sha);
+        }
+        Ok(Self {
+            value,
+            build_number,
+        })
+    }
+
+    /// Persists the build number so that the next bake does not reuse it.
+    pub fn record(&self) -> task::Result<()> {
+        match self.build_number {
+            None => task::SUCCESS,
+            Some(build_number) => GeneratedRoot.write(
+                None, Self::BUILD_NUMBER_FILENAME, build_number.to_string().as_str()),
+        }
+    }
+
+    fn last_build_number() -> task::Result<u32> {
+        let path = GeneratedRoot.join_path(Self::BUILD_NUMBER_FILENAME);
+        if !path.exists() {
+            return Ok(0);
+        }
+        match fs::read_to_string(&path)?.trim().parse() {
+            Ok(build_number) => Ok(build_number),
+            Err(_) => task::Error::result(format!("{} is not a valid build number file", path.display())),
+        }
+    }
+
+    fn utc_timestamp() -> task::Result<String> {
+        let format = format_description!("[year][month][day][hour][minute][second]");
+        match OffsetDateTime::now_utc().format(&format) {
+            Ok(timestamp) => Ok(timestamp),
+            Err(e) => task::Error::result(e.to_string()),
         }
     }
 }
 
 pub struct BakingPlan {
-    pub default_archetype: ImageSpec,
+    pub archetypes: Vec<ImageSpec>,
+    pub instances: Vec<InstanceImagePlan>,
+    pub version: ImageVersion,
 }
 
 impl BakingPlan {
     pub fn from(ctx: &SwoonContext) -> task::Result<Self> {
-        let cfg = ctx.config();
-        Ok(BakingPlan {
-            default_archetype: ImageSpec {
-                source: ImageSource::OperatingSystem { os: cfg.default_os.clone() },
-                platform: cfg.default_platform.clone(),
+        Self::from_config(ctx.config(), ImageVersion::next(ctx)?)
+    }
+
+    fn from_config(cfg: &SwoonConfig, version: ImageVersion) -> task::Result<Self> {
+        let mut archetypes = Vec::new();
+        for platform in &cfg.archetype.platforms {
+            for os in &cfg.archetype.operating_systems {
+                archetypes.push(ImageSpec::archetype(platform, os, &version));
             }
+        }
+        let instances = cfg.instances.iter()
+            .map(|instance| InstanceImagePlan {
+                name: instance.name.clone(),
+                os: instance.os.clone(),
+                platforms: cfg.archetype.platforms.clone(),
+                playbook: instance.playbook.clone(),
+                version: version.value.clone(),
+            })
+            .collect();
+        Ok(BakingPlan {
+            archetypes,
+            instances,
+            version,
         })
     }
+
+    /// Builds archetypes from the source images locked in swoon.lock.
+    pub fn pin_source_images(&mut self, source_lock: &SourceLock) {
+        self.archetypes = self.archetypes.iter()
+            .map(|spec| source_lock.pin(spec))
+            .collect();
+    }
 }
 
-// .swoon/images/{platform}/archetypes/{os}/{image_name}/{files}
-// .swoon/images/gcp/archetypes/debian_11/debian_11-archetype-2022-01-04/{files}
-// .swoon/images/gcp/archetypes/ubuntu_2004_minimal/debian_11-archetype-2022-01-04/{files}
+pub fn image_name_prefix(cfg: &SwoonConfig, label: &str, os: &OperatingSystem) -> String {
+    match cfg.environment.as_ref().and_then(|environment| environment.name_suffix.as_ref()) {
+        None => format!("{}-{}-{}-", cfg.org_name, label, os.slug()),
+        Some(name_suffix) => format!("{}-{}-{}-{}-", cfg.org_name, label, os.slug(), name_suffix),
+    }
+}
 
-// .swoon/images/{platform}/instances/{instance_label}/{image_name}/{files}
-// .swoon/images/gcp/instances/postgres/{instance_label}/postgres-2022-01-04/{files}
+/// Image name prefix without its trailing dash, naming the family of images it prefixes.
+pub fn image_family(cfg: &SwoonConfig, label: &str, os: &OperatingSystem) -> String {
+    image_name_prefix(cfg, label, os).trim_end_matches('-').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::{DEBIAN_11, UBUNTU_2004};
+    use crate::api::config::{ArchetypeConfig, BinariesConfig, EnvironmentConfig, ImageNamingConfig, ImageRetentionConfig, InstanceConfig};
+    use crate::platforms::PlatformConfigs;
+
+    use super::*;
+
+    fn config() -> SwoonConfig {
+        SwoonConfig {
+            org_name: "eighty4".to_string(),
+            default_os: DEBIAN_11,
+            default_platform: CloudPlatform::GCP,
+            archetype: ArchetypeConfig {
+                platforms: vec!(CloudPlatform::GCP, CloudPlatform::AWS),
+                operating_systems: vec!(DEBIAN_11, UBUNTU_2004),
+            },
+            environments: Vec::new(),
+            environment: None,
+            image_naming: ImageNamingConfig::default(),
+            image_retention: ImageRetentionConfig::default(),
+            pin_source_images: false,
+            instances: vec!(InstanceConfig {
+                name: "postgres".to_string(),
+                os: DEBIAN_11,
+                playbook: "postgres.yml".to_string(),
+            }),
+            platforms: PlatformConfigs::default(),
+            binaries: BinariesConfig::default(),
+        }
+    }
+
+    fn version() -> ImageVersion {
+        ImageVersion {
+            value: "20220104153012".to_string(),
+            build_number: None,
+        }
+    }
+
+    #[test]
+    fn test_baking_plan_expands_archetype_matrix() {
+        let plan = BakingPlan::from_config(&config(), version()).unwrap();
+        let specs: Vec<String> = plan.archetypes.iter()
+            .map(|spec| format!("{} on {}", spec.source_string(), spec.platform.to_str()))
+            .collect();
+        assert_eq!(specs, vec!(
+            "debian:11 on gcp",
+            "ubuntu:20.04 on gcp",
+            "debian:11 on aws",
+            "ubuntu:20.04 on aws",
+        ));
+    }
+
+    #[test]
+    fn test_baking_plan_builds_instances_from_archetype() {
+        let cfg = config();
+        let plan = BakingPlan::from_config(&cfg, version()).unwrap();
+        assert_eq!(plan.instances.len(), 1);
+        let postgres = &plan.instances[0];
+        assert_eq!(postgres.platforms, vec!(CloudPlatform::GCP, CloudPlatform::AWS));
+        assert_eq!(postgres.archetype_name_prefix(&cfg), "eighty4-archetype-debian-11-");
+        assert_eq!(postgres.archetype_family(&cfg), "eighty4-archetype-debian-11");
+    }
+
+    #[test]
+    fn test_image_spec_image_name() {
+        let spec = ImageSpec::archetype(&CloudPlatform::GCP, &UBUNTU_2004, &version());
+        assert_eq!(spec.image_name(&config()), "eighty4-archetype-ubuntu-2004-20220104153012");
+    }
+
+    #[test]
+    fn test_image_spec_image_name_with_environment_suffix() {
+        let cfg = SwoonConfig {
+            environment: Some(EnvironmentConfig {
+                name: "staging".to_string(),
+                project: None,
+                account: None,
+                region: None,
+                zone: None,
+                name_suffix: Some("stg".to_string()),
+            }),
+            ..config()
+        };
+        let spec = ImageSpec::archetype(&CloudPlatform::GCP, &UBUNTU_2004, &version());
+        assert_eq!(spec.image_name(&cfg), "eighty4-archetype-ubuntu-2004-stg-20220104153012");
+    }
+
+    #[test]
+    fn test_image_spec_image_family() {
+        let spec = ImageSpec::archetype(&CloudPlatform::GCP, &DEBIAN_11, &version());
+        assert_eq!(spec.image_family(&config()), "eighty4-archetype-debian-11");
+    }
+}
@@ -0,0 +1,145 @@
+use crate::api::{OperatingSystem, task};
+use crate::api::binaries::PathLookup;
+use crate::api::process::Process;
+use crate::api::util::{DataDir, ProjectDir};
+use crate::SwoonContext;
+
+/// Commit of the project repo that an image was baked from.
+#[derive(Clone)]
+pub struct GitState {
+    /// None when the repo has no commits yet.
+    pub commit: Option<String>,
+    /// Whether the project had uncommitted changes, leaving out swoon's generated files.
+    pub dirty: bool,
+}
+
+impl GitState {
+    /// Reads the commit checked out in the project dir, or None when the project is not in a git repo.
+    pub fn read(ctx: &SwoonContext) -> task::Result<Option<Self>> {
+        let project_dir = ProjectDir::path();
+        if !project_dir.ancestors().any(|dir| dir.join(".git").exists()) {
+            return Ok(None);
+        }
+        let commit = if Process::succeeds(&ctx.git_path()?, ["rev-parse", "--verify", "--quiet", "HEAD"])? {
+            Some(Process::invoke(&ctx.git_path()?, ["rev-parse", "HEAD"])?)
+        } else {
+            None
+        };
+        let generated_dir = DataDir::path();
+        let changes = Process::invoke(&ctx.git_path()?, [
+            "status",
+            "--porcelain",
+            "--",
+            ".",
+            format!(":(exclude){}", generated_dir.display()).as_str(),
+        ])?;
+        Ok(Some(Self {
+            commit,
+            dirty: !changes.is_empty(),
+        }))
+    }
+}
+
+/// Label holding the inputs hash of an image, read back to tell whether an image is unchanged
+/// on checkouts without the image archive.
+pub const INPUTS_HASH_LABEL: &str = "swoon-inputs-hash";
+
+/// Inputs hashes stamped on platforms whose label values can't fit a sha256 hex digest are
+/// shortened to a prefix of this many chars of the inputs_hash recorded in the image archive.
+pub const SHORT_INPUTS_HASH_LEN: usize = 32;
+
+/// Where an image came from, stamped on the image as labels or tags so that a running VM
+/// can be traced back to the exact config that produced it.
+pub struct Provenance {
+    pub inputs_hash: String,
+    pub git: Option<GitState>,
+    pub source_image: String,
+    pub os: OperatingSystem,
+}
+
+impl Provenance {
+    /// Label names with values not yet sanitized to the rules of a platform.
+    pub fn labels(&self) -> Vec<(&'static str, String)> {
+        let mut labels = vec!(
+            ("swoon-version", env!("CARGO_PKG_VERSION").to_string()),
+            (INPUTS_HASH_LABEL, self.inputs_hash.clone()),
+        );
+        if let Some(git) = &self.git {
+            if let Some(commit) = &git.commit {
+                labels.push(("swoon-git-commit", commit.clone()));
+            }
+            labels.push(("swoon-git-dirty", git.dirty.to_string()));
+        }
+        labels.push(("swoon-source-image", self.source_image.clone()));
+        labels.push(("swoon-os", self.os.to_string()));
+        labels
+    }
+
+    /// Labels sanitized to a platform's rules for label values.
+    pub fn sanitized_labels<F>(&self, sanitize: F) -> Vec<(String, String)>
+        where F: Fn(&str) -> String {
+        self.labels().into_iter()
+            .map(|(name, value)| (name.to_string(), sanitize(&value)))
+            .collect()
+    }
+
+    /// Labels sanitized to a platform's rules for label values, with the inputs hash shortened
+    /// to [SHORT_INPUTS_HASH_LEN] chars so that sanitizing never truncates it.
+    pub fn sanitized_short_labels<F>(&self, sanitize: F) -> Vec<(String, String)>
+        where F: Fn(&str) -> String {
+        self.sanitized_labels(sanitize).into_iter()
+            .map(|(name, value)| if name == INPUTS_HASH_LABEL {
+                (name, value.chars().take(SHORT_INPUTS_HASH_LEN).collect())
+            } else {
+                (name, value)
+            })
+            .collect()
+    }
+
+    /// Labels written as space separated `name=value` pairs for platforms that only describe images.
+    pub fn description(&self) -> String {
+        self.labels().iter()
+            .map(|(name, value)| format!("{}={}", name, value.replace(char::is_whitespace, "-")))
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::DEBIAN_11;
+
+    use super::*;
+
+    #[test]
+    fn test_provenance_labels() {
+        let mut provenance = Provenance {
+            inputs_hash: "abc".to_string(),
+            git: Some(GitState {
+                commit: Some("0123456789abcdef".to_string()),
+                dirty: true,
+            }),
+            source_image: "debian-11-bullseye-v20211209".to_string(),
+            os: DEBIAN_11,
+        };
+        let names = |provenance: &Provenance| provenance.labels().iter().map(|(name, _)| *name).collect::<Vec<&str>>();
+        assert_eq!(names(&provenance), vec!(
+            "swoon-version",
+            "swoon-inputs-hash",
+            "swoon-git-commit",
+            "swoon-git-dirty",
+            "swoon-source-image",
+            "swoon-os",
+        ));
+        assert!(provenance.labels().contains(&("swoon-git-dirty", "true".to_string())));
+        assert!(provenance.labels().contains(&("swoon-os", "debian:11".to_string())));
+        provenance.git.as_mut().unwrap().commit = None;
+        assert!(!names(&provenance).contains(&"swoon-git-commit"));
+        assert!(names(&provenance).contains(&"swoon-git-dirty"));
+        provenance.git = None;
+        assert!(!names(&provenance).contains(&"swoon-git-commit"));
+        assert_eq!(provenance.description(), format!(
+            "swoon-version={} swoon-inputs-hash=abc swoon-source-image=debian-11-bullseye-v20211209 swoon-os=debian:11",
+            env!("CARGO_PKG_VERSION")));
+    }
+}
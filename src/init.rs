@@ -4,10 +4,10 @@ use crate::ansible;
 use crate::api::{CloudPlatform, command, DEFAULT_OS, OperatingSystem, task};
 use crate::api::CloudPlatform::*;
 use crate::api::command::Name::Bake;
-use crate::api::config::SwoonConfig;
+use crate::api::config::{ArchetypeConfig, BinariesConfig, ImageNamingConfig, ImageRetentionConfig, SwoonConfig};
 use crate::api::context::SwoonContext;
 use crate::api::util::DataDir;
-use crate::packer::PackerBuild;
+use crate::platforms::PlatformConfigs;
 
 pub struct InitOpts<'a> {
     pub non_interactive: bool,
@@ -19,7 +19,6 @@ pub struct InitOpts<'a> {
 
 pub fn init_swoon_project(ctx: &SwoonContext, opts: &InitOpts) -> command::Result {
     if ctx.has_config() {
-        // todo[speed] build platform ctx for this error is unnecessary
         return command::Error::with_command_suggestions(
             "A swoon.yml file already exists in your current directory",
             vec!(Bake),
@@ -27,13 +26,12 @@ pub fn init_swoon_project(ctx: &SwoonContext, opts: &InitOpts) -> command::Resul
     }
     DataDir::init()?;
 
-    let new_ctx = &ctx.with_config(build_project_config(opts)?);
-    new_ctx.config().write(opts.template_name)?;
+    build_project_config(opts)?.write(opts.template_name)?;
 
+    // packer config files are written by swoon bake so that init does not need the platform's cli
+    ansible::init_archetype_playbook()?;
     ansible::init_roles_dir()?;
 
-    PackerBuild::default_archetype(new_ctx).write_config_files()?;
-
     command::SUCCESS
 }
 
@@ -59,9 +57,18 @@ fn resolve_config_from_opts(opts: &InitOpts) -> task::Result<SwoonConfig> {
         Some(s) => CloudPlatform::from_str(s),
     };
     Ok(SwoonConfig {
+        archetype: ArchetypeConfig::from_defaults(&default_platform, &default_os),
+        environments: Vec::new(),
+        environment: None,
         org_name,
         default_os,
         default_platform,
+        image_naming: ImageNamingConfig::default(),
+        image_retention: ImageRetentionConfig::default(),
+        instances: Vec::new(),
+        pin_source_images: false,
+        platforms: PlatformConfigs::default(),
+        binaries: BinariesConfig::default(),
     })
 }
 
@@ -79,9 +86,18 @@ fn prompt_for_config(opts: &InitOpts) -> task::Result<SwoonConfig> {
         Some(s) => CloudPlatform::from_str(s),
     };
     Ok(SwoonConfig {
+        archetype: ArchetypeConfig::from_defaults(&default_platform, &default_os),
+        environments: Vec::new(),
+        environment: None,
         org_name,
         default_os,
         default_platform,
+        image_naming: ImageNamingConfig::default(),
+        image_retention: ImageRetentionConfig::default(),
+        instances: Vec::new(),
+        pin_source_images: false,
+        platforms: PlatformConfigs::default(),
+        binaries: BinariesConfig::default(),
     })
 }
 
@@ -99,6 +115,10 @@ fn prompt_for_default_platform(org_name: &String) -> task::Result<CloudPlatform>
     let platform_opts = vec![
         GCP.to_str(),
         AWS.to_str(),
+        Azure.to_str(),
+        DigitalOcean.to_str(),
+        Linode.to_str(),
+        Vultr.to_str(),
     ];
     let platform_selection = Select::new()
         .with_prompt(format!("What is {}'s primary cloud platform?", org_name))
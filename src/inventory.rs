@@ -0,0 +1,389 @@
+use dialoguer::Input;
+use time::{Duration, OffsetDateTime};
+
+use crate::api::{CloudPlatform, command, task};
+use crate::api::command::Name::Init;
+use crate::api::config::SwoonConfig;
+use crate::api::context::SwoonContext;
+use crate::api::output::file::Directory::ImageArchive;
+use crate::api::util::format_timestamp;
+use crate::images::{ARCHETYPE_LABEL, image_name_prefix};
+use crate::images::archive::{ArchivedImage, read_archived_images};
+use crate::images::lifecycle::in_family;
+use crate::platforms::images::{self as platform_images, PlatformImage};
+
+pub struct PruneOpts {
+    pub approve_plan: bool,
+}
+
+/// An image found on a cloud platform, in the image archive or both.
+pub struct InventoryImage {
+    pub platform: CloudPlatform,
+    pub name: String,
+    pub platform_image: Option<PlatformImage>,
+    pub archived: Option<ArchivedImage>,
+    /// Whether swoon was able to query the image's platform for its images.
+    queried: bool,
+}
+
+impl InventoryImage {
+    fn created_at(&self) -> Option<OffsetDateTime> {
+        match (&self.platform_image, &self.archived) {
+            (Some(platform_image), _) => Some(platform_image.created_at),
+            (None, Some(archived)) => Some(archived.finished_at),
+            (None, None) => None,
+        }
+    }
+
+    fn status(&self) -> String {
+        match &self.platform_image {
+            Some(platform_image) => platform_image.status.clone(),
+            None if self.queried => "deleted".to_string(),
+            None => "unknown".to_string(),
+        }
+    }
+
+    fn os(&self) -> String {
+        self.archived.as_ref().map_or("-".to_string(), |archived| archived.os.to_string())
+    }
+
+    fn age(&self, now: OffsetDateTime) -> String {
+        self.created_at().map_or("-".to_string(), |created_at| format_age(now - created_at))
+    }
+
+    /// Family of images swoon baked the image into, by the image name prefixes configured in swoon.yml
+    /// or by the image archive for families since removed from swoon.yml. Images with the org's name
+    /// prefix that swoon did not bake have no family.
+    fn family(&self, cfg: &SwoonConfig) -> Option<String> {
+        family_prefix(cfg, &self.name).or_else(|| self.archived.as_ref().map(|archived| format!(
+            "{} {} {}", archived.label, archived.os, archived.environment.as_deref().unwrap_or_default())))
+    }
+}
+
+pub struct Inventory {
+    images: Vec<InventoryImage>,
+}
+
+impl Inventory {
+    /// Queries each platform for images with the org's naming prefix and joins them with the image archive.
+    /// Platforms removed from swoon.yml are still queried while the archive has images baked on them.
+    pub fn load(ctx: &SwoonContext) -> task::Result<Self> {
+        let cfg = ctx.config();
+        let prefix = format!("{}-", cfg.org_name);
+        let archived_images = read_archived_images()?;
+        let mut platforms_images = Vec::new();
+        for platform in cfg.archetype.platforms.iter().chain(archived_images.iter().map(|archived| &archived.platform)) {
+            if !platforms_images.iter().any(|(p, _)| p == platform) {
+                platforms_images.push((platform.clone(), platform_images::images_by_prefix(ctx, platform, &prefix)?));
+            }
+        }
+        Ok(Self::join(archived_images, platforms_images))
+    }
+
+    fn join(archived_images: Vec<ArchivedImage>,
+            platforms_images: Vec<(CloudPlatform, Option<Vec<PlatformImage>>)>) -> Self {
+        let mut images: Vec<InventoryImage> = Vec::new();
+        let mut queried_platforms = Vec::new();
+        for (platform, platform_images_opt) in platforms_images {
+            if platform_images_opt.is_some() {
+                queried_platforms.push(platform.clone());
+            }
+            for platform_image in platform_images_opt.into_iter().flatten() {
+                images.push(InventoryImage {
+                    platform: platform.clone(),
+                    name: platform_image.name.clone(),
+                    platform_image: Some(platform_image),
+                    archived: None,
+                    queried: true,
+                });
+            }
+        }
+        for archived in archived_images {
+            match images.iter_mut().find(|image| image.platform == archived.platform && image.name == archived.image_name) {
+                Some(image) => image.archived = Some(archived),
+                None => images.push(InventoryImage {
+                    platform: archived.platform.clone(),
+                    name: archived.image_name.clone(),
+                    platform_image: None,
+                    queried: queried_platforms.contains(&archived.platform),
+                    archived: Some(archived),
+                }),
+            }
+        }
+        images.sort_by(|a, b| (a.platform.to_str(), &a.name).cmp(&(b.platform.to_str(), &b.name)));
+        Self { images }
+    }
+
+    /// Images of swoon's image families on a platform older than the max age, excluding the latest
+    /// image of each image family.
+    fn prune_candidates(&self, cfg: &SwoonConfig, now: OffsetDateTime, max_age_days: u32) -> Vec<&InventoryImage> {
+        let max_age = Duration::days(max_age_days as i64);
+        let on_platform = || self.images.iter().filter(|image| image.platform_image.is_some());
+        on_platform()
+            .filter(|image| now - image.created_at().unwrap() > max_age)
+            .filter(|image| match image.family(cfg) {
+                None => false,
+                Some(family) => on_platform().any(|other| other.platform == image.platform
+                    && other.family(cfg).as_ref() == Some(&family)
+                    && other.created_at() > image.created_at()),
+            })
+            .collect()
+    }
+}
+
+/// Image name prefix of the archetype or instance image family configured in swoon.yml that an image was baked into.
+fn family_prefix(cfg: &SwoonConfig, image_name: &str) -> Option<String> {
+    cfg.archetype.operating_systems.iter()
+        .map(|os| image_name_prefix(cfg, ARCHETYPE_LABEL, os))
+        .chain(cfg.instances.iter().map(|instance| image_name_prefix(cfg, &instance.name, &instance.os)))
+        .filter(|prefix| in_family(prefix, image_name))
+        .max_by_key(|prefix| prefix.len())
+}
+
+fn format_age(age: Duration) -> String {
+    if age.whole_days() > 0 {
+        format!("{}d", age.whole_days())
+    } else if age.whole_hours() > 0 {
+        format!("{}h", age.whole_hours())
+    } else {
+        format!("{}m", age.whole_minutes())
+    }
+}
+
+fn require_config(ctx: &SwoonContext) -> command::Result {
+    if !ctx.has_config() {
+        return command::Error::with_command_suggestions(
+            "There's no swoon.yml file in your current directory",
+            vec!(Init),
+        );
+    }
+    command::SUCCESS
+}
+
+pub fn list_images(ctx: &SwoonContext) -> command::Result {
+    require_config(ctx)?;
+    let inventory = Inventory::load(ctx)?;
+    if inventory.images.is_empty() {
+        ctx.write_line("No images have been baked");
+        return command::SUCCESS;
+    }
+    let now = OffsetDateTime::now_utc();
+    let name_width = inventory.images.iter().map(|image| image.name.len()).max().unwrap_or_default();
+    ctx.write_line(format!("{:<12} {:<name_width$} {:<20} {:>5}  STATUS", "PLATFORM", "NAME", "OS", "AGE"));
+    for image in &inventory.images {
+        ctx.write_line(format!("{:<12} {:<name_width$} {:<20} {:>5}  {}",
+                               image.platform.to_str(),
+                               image.name,
+                               image.os(),
+                               image.age(now),
+                               image.status()));
+    }
+    command::SUCCESS
+}
+
+pub fn show_image(ctx: &SwoonContext, image_name: &str) -> command::Result {
+    require_config(ctx)?;
+    let inventory = Inventory::load(ctx)?;
+    let images: Vec<&InventoryImage> = inventory.images.iter().filter(|image| image.name == image_name).collect();
+    if images.is_empty() {
+        return command::Error::result(format!("No image named {} was found", image_name).as_str());
+    }
+    let now = OffsetDateTime::now_utc();
+    for image in images {
+        ctx.write_line(format!("{} on {}", image.name, image.platform.to_str()));
+        if let Some(platform_image) = &image.platform_image {
+            ctx.write_line(format!("    image id:      {}", platform_image.id));
+        }
+        ctx.write_line(format!("    status:        {}", image.status()));
+        let has_image_families = ctx.platforms.provider(&image.platform)?.has_image_families();
+        if let Some(family) = family_prefix(ctx.config(), &image.name).filter(|_| has_image_families) {
+            ctx.write_line(format!("    image family:  {}", family.trim_end_matches('-')));
+        }
+        if let Some(created_at) = image.created_at() {
+            ctx.write_line(format!("    created:       {} ({} ago)", format_timestamp(&created_at)?, image.age(now)));
+        }
+        match &image.archived {
+            None => ctx.write_line("    archive:       not archived"),
+            Some(archived) => {
+                ctx.write_line(format!("    source os:     {}", archived.os));
+                ctx.write_line(format!("    source image:  {}", archived.source_image));
+                ctx.write_line(format!("    bake time:     {}s", archived.duration_seconds()));
+                ctx.write_line(format!("    archive:       {}", ImageArchive.path().join(archived.archive_path()).display()));
+            }
+        }
+    }
+    command::SUCCESS
+}
+
+pub fn prune_images(ctx: &SwoonContext, opts: &PruneOpts) -> command::Result {
+    require_config(ctx)?;
+    let max_age_days = match ctx.config().image_retention.max_age_days {
+        Some(max_age_days) => max_age_days,
+        None => return command::Error::result("Pruning images requires image_retention max_age_days in swoon.yml"),
+    };
+    let inventory = Inventory::load(ctx)?;
+    let candidates = inventory.prune_candidates(ctx.config(), OffsetDateTime::now_utc(), max_age_days);
+    if candidates.is_empty() {
+        ctx.write_line(format!("No images older than {} days to prune", max_age_days));
+        return command::SUCCESS;
+    }
+    if !opts.approve_plan && !prompt_for_approval(ctx, max_age_days, &candidates) {
+        ctx.write_line("Cancelling prune plan");
+        return command::SUCCESS;
+    }
+    for image in candidates {
+        ctx.write_line(format!("Deleting {} on {}", image.name, image.platform.to_str()));
+        platform_images::delete_image(ctx, &image.platform, image.platform_image.as_ref().unwrap())?;
+    }
+    command::SUCCESS
+}
+
+fn prompt_for_approval(ctx: &SwoonContext, max_age_days: u32, candidates: &[&InventoryImage]) -> bool {
+    ctx.write_line(format!("Deleting images older than {} days:", max_age_days));
+    for image in candidates {
+        ctx.write_line(format!("    {} on {}", image.name, image.platform.to_str()));
+    }
+    let result = Input::<String>::new()
+        .with_prompt("Type yes to delete images")
+        .allow_empty(true)
+        .interact_text();
+    match result {
+        Ok(approval) => approval == "yes",
+        Err(e) => task::Error::from(e).exit(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use crate::api::{DEBIAN_10, DEBIAN_11, OperatingSystem, UBUNTU_2004};
+    use crate::api::config::{ArchetypeConfig, BinariesConfig, ImageNamingConfig, ImageRetentionConfig, InstanceConfig};
+    use crate::platforms::PlatformConfigs;
+
+    use super::*;
+
+    fn config() -> SwoonConfig {
+        SwoonConfig {
+            org_name: "eighty4".to_string(),
+            default_os: DEBIAN_11,
+            default_platform: CloudPlatform::GCP,
+            archetype: ArchetypeConfig {
+                platforms: vec!(CloudPlatform::GCP),
+                operating_systems: vec!(DEBIAN_11, UBUNTU_2004),
+            },
+            environments: Vec::new(),
+            environment: None,
+            image_naming: ImageNamingConfig::default(),
+            image_retention: ImageRetentionConfig::default(),
+            pin_source_images: false,
+            instances: vec!(InstanceConfig {
+                name: "postgres".to_string(),
+                os: DEBIAN_11,
+                playbook: "postgres.yml".to_string(),
+            }),
+            platforms: PlatformConfigs::default(),
+            binaries: BinariesConfig::default(),
+        }
+    }
+
+    fn platform_image(name: &str, created_at: OffsetDateTime) -> PlatformImage {
+        PlatformImage {
+            name: name.to_string(),
+            id: "1234567890".to_string(),
+            created_at,
+            status: "ready".to_string(),
+            inputs_hash: None,
+        }
+    }
+
+    fn archived_image(name: &str) -> ArchivedImage {
+        archived_image_of(name, DEBIAN_11)
+    }
+
+    fn archived_image_of(name: &str, os: OperatingSystem) -> ArchivedImage {
+        ArchivedImage {
+            image_name: name.to_string(),
+            image_id: name.to_string(),
+            inputs_hash: None,
+            environment: None,
+            promoted_from: None,
+            label: ARCHETYPE_LABEL.to_string(),
+            platform: CloudPlatform::GCP,
+            os,
+            source_image: "debian:11".to_string(),
+            started_at: datetime!(2022-01-04 15:30:12 UTC),
+            finished_at: datetime!(2022-01-04 15:42:30 UTC),
+        }
+    }
+
+    #[test]
+    fn test_inventory_joins_platform_images_with_archive() {
+        let inventory = Inventory::join(
+            vec!(archived_image("eighty4-archetype-debian-11-1"), archived_image("eighty4-archetype-debian-11-2")),
+            vec!((CloudPlatform::GCP, Some(vec!(
+                platform_image("eighty4-archetype-debian-11-2", datetime!(2022-01-04 15:42:30 UTC)),
+                platform_image("eighty4-postgres-debian-11-2", datetime!(2022-01-04 16:02:00 UTC)),
+            )))),
+        );
+        let names: Vec<&str> = inventory.images.iter().map(|image| image.name.as_str()).collect();
+        assert_eq!(names, vec!("eighty4-archetype-debian-11-1", "eighty4-archetype-debian-11-2", "eighty4-postgres-debian-11-2"));
+        assert_eq!(inventory.images[0].status(), "deleted");
+        assert_eq!(inventory.images[1].status(), "ready");
+        assert_eq!(inventory.images[1].os(), "debian:11");
+        assert_eq!(inventory.images[2].os(), "-");
+    }
+
+    #[test]
+    fn test_inventory_image_status_unknown_without_platform_query() {
+        let inventory = Inventory::join(vec!(archived_image("eighty4-archetype-debian-11-1")), Vec::new());
+        assert_eq!(inventory.images[0].status(), "unknown");
+    }
+
+    #[test]
+    fn test_prune_candidates_keeps_latest_image_of_each_family() {
+        let inventory = Inventory::join(Vec::new(), vec!((CloudPlatform::GCP, Some(vec!(
+            platform_image("eighty4-archetype-debian-11-1", datetime!(2022-01-01 00:00 UTC)),
+            platform_image("eighty4-archetype-debian-11-2", datetime!(2022-01-02 00:00 UTC)),
+            platform_image("eighty4-archetype-debian-11-3", datetime!(2022-02-20 00:00 UTC)),
+            platform_image("eighty4-postgres-debian-11-1", datetime!(2022-01-01 00:00 UTC)),
+            platform_image("eighty4-archetype-ubuntu-2004-1", datetime!(2022-01-01 00:00 UTC)),
+            platform_image("eighty4-archetype-ubuntu-2004-2", datetime!(2022-01-02 00:00 UTC)),
+        )))));
+        let candidates: Vec<&str> = inventory.prune_candidates(&config(), datetime!(2022-03-01 00:00 UTC), 30)
+            .iter()
+            .map(|image| image.name.as_str())
+            .collect();
+        assert_eq!(candidates, vec!("eighty4-archetype-debian-11-1", "eighty4-archetype-debian-11-2", "eighty4-archetype-ubuntu-2004-1"));
+    }
+
+    #[test]
+    fn test_prune_candidates_keeps_images_swoon_did_not_bake() {
+        let inventory = Inventory::join(
+            vec!(
+                archived_image_of("eighty4-archetype-debian-10-1", DEBIAN_10),
+                archived_image_of("eighty4-archetype-debian-10-2", DEBIAN_10),
+            ),
+            vec!((CloudPlatform::GCP, Some(vec!(
+                platform_image("eighty4-foo-1", datetime!(2022-01-01 00:00 UTC)),
+                platform_image("eighty4-foo-2", datetime!(2022-01-02 00:00 UTC)),
+                platform_image("eighty4-archetype-debian-11-minimal-1", datetime!(2022-01-01 00:00 UTC)),
+                platform_image("eighty4-archetype-debian-10-1", datetime!(2022-01-01 00:00 UTC)),
+                platform_image("eighty4-archetype-debian-10-2", datetime!(2022-01-02 00:00 UTC)),
+                platform_image("eighty4-archetype-debian-10-3", datetime!(2022-01-03 00:00 UTC)),
+            )))),
+        );
+        let candidates: Vec<&str> = inventory.prune_candidates(&config(), datetime!(2022-03-01 00:00 UTC), 30)
+            .iter()
+            .map(|image| image.name.as_str())
+            .collect();
+        assert_eq!(candidates, vec!("eighty4-archetype-debian-10-1"));
+    }
+
+    #[test]
+    fn test_format_age() {
+        assert_eq!(format_age(Duration::days(3) + Duration::hours(4)), "3d");
+        assert_eq!(format_age(Duration::hours(5)), "5h");
+        assert_eq!(format_age(Duration::minutes(12)), "12m");
+    }
+}
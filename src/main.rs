@@ -1,19 +1,23 @@
 use std::process::exit;
 
-use clap;
-
 use crate::api::command;
 use crate::api::context::SwoonContext;
 use crate::bake::{bake_machine_images, BakeOpts};
 use crate::init::{init_swoon_project, InitOpts};
+use crate::inventory::{list_images, prune_images, PruneOpts, show_image};
+use crate::outdated::report_outdated_images;
+use crate::promote::{promote_image, PromoteOpts};
 
 mod ansible;
 mod api;
 mod bake;
 mod images;
 mod init;
+mod inventory;
+mod outdated;
 mod packer;
 mod platforms;
+mod promote;
 
 fn main() {
     let a: clap::ArgMatches = clap::Command::new("Swoon CLI")
@@ -27,6 +31,13 @@ fn main() {
             .help("Print extra debugging info")
             .takes_value(false))
 
+        .arg(clap::Arg::new("env")
+            .long("env")
+            .value_name("ENV")
+            .help("Environment from swoon.yml to use, defaulting to SWOON_ENV")
+            .global(true)
+            .takes_value(true))
+
         .subcommand(clap::Command::new("init")
             .about("init your cloud config")
 
@@ -52,7 +63,7 @@ fn main() {
                 .long("cloud-platform")
                 .value_name("PLATFORM")
                 .help("Cloud platform")
-                .possible_values(&["gcp"])
+                .possible_values(["aws", "azure", "digitalocean", "gcp", "linode", "vultr"])
                 .takes_value(true))
 
             .arg(clap::Arg::new("operating-system")
@@ -69,16 +80,78 @@ fn main() {
                 .long("approve-plan")
                 .help("Approve machine image plan")
                 .takes_value(false))
+
+            .arg(clap::Arg::new("force")
+                .short('f')
+                .long("force")
+                .help("Rebake images with unchanged inputs")
+                .takes_value(false))
+
+            .arg(clap::Arg::new("update-sources")
+                .long("update-sources")
+                .help("Resolve the latest OS images for source images pinned in swoon.lock")
+                .takes_value(false))
+        )
+        .subcommand(clap::Command::new("images")
+            .about("list, inspect and prune your machine images")
+            .subcommand_required(true)
+            .arg_required_else_help(true)
+
+            .subcommand(clap::Command::new("list")
+                .about("list images on your cloud platforms and in the image archive"))
+
+            .subcommand(clap::Command::new("show")
+                .about("show the details of an image")
+
+                .arg(clap::Arg::new("image")
+                    .value_name("IMAGE")
+                    .help("Image name")
+                    .required(true)))
+
+            .subcommand(clap::Command::new("prune")
+                .about("delete images older than the image_retention policy in swoon.yml")
+
+                .arg(clap::Arg::new("approve-plan")
+                    .short('a')
+                    .long("approve-plan")
+                    .help("Approve image prune plan")
+                    .takes_value(false)))
+        )
+        .subcommand(clap::Command::new("outdated")
+            .about("find images based on outdated OS images")
+        )
+        .subcommand(clap::Command::new("promote")
+            .about("copy an image into another environment without rebaking it")
+
+            .arg(clap::Arg::new("image")
+                .value_name("IMAGE")
+                .help("Image name")
+                .required(true))
+
+            .arg(clap::Arg::new("to")
+                .long("to")
+                .value_name("ENV")
+                .help("Environment from swoon.yml to promote the image to")
+                .required(true)
+                .takes_value(true))
+
+            .arg(clap::Arg::new("approve-plan")
+                .short('a')
+                .long("approve-plan")
+                .help("Approve image promotion")
+                .takes_value(false))
         )
         .get_matches();
 
-    let c: SwoonContext = SwoonContext::init_from_args(&a)
-        .expect("failed to initialize");
+    let c: SwoonContext = match SwoonContext::init_from_args(&a) {
+        Ok(c) => c,
+        Err(e) => e.exit(),
+    };
 
     let r: command::Result = exec_cmd(&c, &a);
     if let Some(err) = r.err() {
         let error_msg = &err.cause.to_string();
-        if err.alt_commands.len() > 0 {
+        if !err.alt_commands.is_empty() {
             c.write_line(format!("{}. Try these commands:", error_msg).as_str());
             for alt_command in err.alt_commands {
                 c.write_line(format!("    swoon {0}\n    swoon help {0}", alt_command).as_str());
@@ -95,15 +168,31 @@ fn exec_cmd(ctx: &SwoonContext, args: &clap::ArgMatches) -> command::Result {
         None => command::SUCCESS,
         Some((subcommand_name, subcommand_args)) => {
             match subcommand_name {
-                "init" => init_swoon_project(&ctx, &InitOpts {
+                "init" => init_swoon_project(ctx, &InitOpts {
                     non_interactive: subcommand_args.is_present("non-interactive"),
                     template_name: subcommand_args.value_of("template"),
                     org_name: subcommand_args.value_of("org-name"),
                     default_platform: subcommand_args.value_of("cloud-platform"),
                     default_os: subcommand_args.value_of("operating-system"),
                 }),
-                "bake" => bake_machine_images(&ctx, &BakeOpts {
+                "bake" => bake_machine_images(ctx, &BakeOpts {
                     approve_plan: subcommand_args.is_present("approve-plan"),
+                    force: subcommand_args.is_present("force"),
+                    update_sources: subcommand_args.is_present("update-sources"),
+                }),
+                "images" => match subcommand_args.subcommand() {
+                    Some(("list", _)) => list_images(ctx),
+                    Some(("show", show_args)) => show_image(ctx, show_args.value_of("image").unwrap()),
+                    Some(("prune", prune_args)) => prune_images(ctx, &PruneOpts {
+                        approve_plan: prune_args.is_present("approve-plan"),
+                    }),
+                    _ => command::SUCCESS,
+                },
+                "outdated" => report_outdated_images(ctx),
+                "promote" => promote_image(ctx, &PromoteOpts {
+                    approve: subcommand_args.is_present("approve-plan"),
+                    image_name: subcommand_args.value_of("image").unwrap(),
+                    to: subcommand_args.value_of("to").unwrap(),
                 }),
                 &_ => command::SUCCESS,
             }
@@ -0,0 +1,218 @@
+use crate::api::{CloudPlatform, command, OperatingSystem, task};
+use crate::api::command::Name::Init;
+use crate::api::context::SwoonContext;
+use crate::images::ARCHETYPE_LABEL;
+use crate::images::archive::{ArchivedImage, read_archived_images};
+use crate::platforms::images as platform_images;
+
+/// The OS image the latest bake of an image was based on, compared with the current image for the OS.
+struct SourceCheck<'a> {
+    image: &'a ArchivedImage,
+    /// For instance images, the OS image their archetype image was built from.
+    built_from: Option<&'a str>,
+    current: Option<String>,
+}
+
+impl<'a> SourceCheck<'a> {
+    fn is_outdated(&self) -> bool {
+        match (self.built_from, &self.current) {
+            (Some(built_from), Some(current)) => built_from != current,
+            _ => false,
+        }
+    }
+
+    fn status(&self) -> &str {
+        match (self.built_from, &self.current) {
+            (Some(_), Some(_)) if self.is_outdated() => "outdated",
+            (Some(_), Some(_)) => "current",
+            _ => "unknown",
+        }
+    }
+}
+
+/// Latest archived bake of each image, so that images replaced by newer bakes are not reported.
+fn latest_bakes(archived_images: &[ArchivedImage]) -> Vec<&ArchivedImage> {
+    let mut latest: Vec<&ArchivedImage> = Vec::new();
+    for archived in archived_images {
+        let same_image = |other: &&ArchivedImage| other.platform == archived.platform
+            && other.label == archived.label
+            && other.os == archived.os;
+        match latest.iter().position(same_image) {
+            None => latest.push(archived),
+            Some(i) if latest[i].finished_at < archived.finished_at => latest[i] = archived,
+            Some(_) => {}
+        }
+    }
+    latest.sort_by(|a, b| (a.platform.to_str(), &a.image_name).cmp(&(b.platform.to_str(), &b.image_name)));
+    latest
+}
+
+/// The OS image an archived image is based on, found through its archetype image for instance images.
+/// Instance images record their archetype by image name, or by image id on platforms like AWS.
+/// Archives written before swoon recorded exact source images only have the OS and are skipped.
+fn built_from<'a>(archived_images: &'a [ArchivedImage], image: &'a ArchivedImage) -> Option<&'a str> {
+    let archetype = if image.label == ARCHETYPE_LABEL {
+        image
+    } else {
+        archived_images.iter().find(|archived| archived.platform == image.platform
+            && archived.label == ARCHETYPE_LABEL
+            && (archived.image_name == image.source_image || archived.image_id == image.source_image))?
+    };
+    if archetype.source_image == archetype.os.to_string() {
+        None
+    } else {
+        Some(archetype.source_image.as_str())
+    }
+}
+
+fn source_checks<'a, F>(archived_images: &'a [ArchivedImage], mut current_os_image: F) -> task::Result<Vec<SourceCheck<'a>>>
+    where F: FnMut(&CloudPlatform, &OperatingSystem) -> task::Result<Option<String>> {
+    let mut current_os_images: Vec<(&CloudPlatform, &OperatingSystem, Option<String>)> = Vec::new();
+    let mut checks = Vec::new();
+    for image in latest_bakes(archived_images) {
+        let current = match current_os_images.iter().find(|(p, os, _)| *p == &image.platform && *os == &image.os) {
+            Some((_, _, current)) => current.clone(),
+            None => {
+                let current = current_os_image(&image.platform, &image.os)?;
+                current_os_images.push((&image.platform, &image.os, current.clone()));
+                current
+            }
+        };
+        checks.push(SourceCheck {
+            image,
+            built_from: built_from(archived_images, image),
+            current,
+        });
+    }
+    Ok(checks)
+}
+
+pub fn report_outdated_images(ctx: &SwoonContext) -> command::Result {
+    if !ctx.has_config() {
+        return command::Error::with_command_suggestions(
+            "There's no swoon.yml file in your current directory",
+            vec!(Init),
+        );
+    }
+    let archived_images = read_archived_images()?;
+    let platforms = &ctx.config().archetype.platforms;
+    let checks = source_checks(&archived_images, |platform, os| {
+        if platforms.contains(platform) {
+            platform_images::current_os_image(ctx, platform, os)
+        } else {
+            Ok(None)
+        }
+    })?;
+    if checks.is_empty() {
+        ctx.write_line("No images have been baked");
+        return command::SUCCESS;
+    }
+    let name_width = checks.iter().map(|check| check.image.image_name.len()).max().unwrap_or_default();
+    let source_width = checks.iter()
+        .flat_map(|check| [check.built_from.unwrap_or("-").len(), check.current.as_deref().unwrap_or("-").len()])
+        .chain(["BUILT FROM".len()])
+        .max().unwrap_or_default();
+    ctx.write_line(format!("{:<12} {:<name_width$} {:<source_width$} {:<source_width$}  STATUS",
+                           "PLATFORM", "IMAGE", "BUILT FROM", "CURRENT"));
+    for check in &checks {
+        ctx.write_line(format!("{:<12} {:<name_width$} {:<source_width$} {:<source_width$}  {}",
+                               check.image.platform.to_str(),
+                               check.image.image_name,
+                               check.built_from.unwrap_or("-"),
+                               check.current.as_deref().unwrap_or("-"),
+                               check.status()));
+    }
+    let outdated = checks.iter().filter(|check| check.is_outdated()).count();
+    if outdated > 0 {
+        ctx.write_line(format!("\n{} images are based on outdated OS images. Run swoon bake to rebake them.", outdated));
+    }
+    command::SUCCESS
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+    use time::OffsetDateTime;
+
+    use crate::api::{DEBIAN_11, UBUNTU_2004};
+
+    use super::*;
+
+    fn archived_image(image_name: &str, label: &str, source_image: &str, finished_at: OffsetDateTime) -> ArchivedImage {
+        ArchivedImage {
+            image_name: image_name.to_string(),
+            image_id: image_name.to_string(),
+            inputs_hash: None,
+            environment: None,
+            promoted_from: None,
+            label: label.to_string(),
+            platform: CloudPlatform::GCP,
+            os: DEBIAN_11,
+            source_image: source_image.to_string(),
+            started_at: finished_at,
+            finished_at,
+        }
+    }
+
+    fn archived_images() -> Vec<ArchivedImage> {
+        vec!(
+            archived_image("eighty4-archetype-debian-11-1", "archetype",
+                           "debian-11-bullseye-v20211105", datetime!(2021-11-10 00:00 UTC)),
+            archived_image("eighty4-archetype-debian-11-2", "archetype",
+                           "debian-11-bullseye-v20211209", datetime!(2021-12-10 00:00 UTC)),
+            archived_image("eighty4-postgres-debian-11-1", "postgres",
+                           "eighty4-archetype-debian-11-1", datetime!(2021-11-10 01:00 UTC)),
+        )
+    }
+
+    #[test]
+    fn test_source_checks_compare_latest_bakes_with_current_os_image() {
+        let archived_images = archived_images();
+        let checks = source_checks(&archived_images, |_, _| Ok(Some("debian-11-bullseye-v20211209".to_string()))).unwrap();
+        assert_eq!(checks.len(), 2);
+        assert_eq!(checks[0].image.image_name, "eighty4-archetype-debian-11-2");
+        assert_eq!(checks[0].status(), "current");
+        assert_eq!(checks[1].image.image_name, "eighty4-postgres-debian-11-1");
+        assert_eq!(checks[1].built_from, Some("debian-11-bullseye-v20211105"));
+        assert_eq!(checks[1].status(), "outdated");
+    }
+
+    #[test]
+    fn test_source_checks_find_aws_archetype_by_image_id() {
+        let mut archetype = archived_image("eighty4-archetype-debian-11-1", "archetype",
+                                           "ami-0a1b2c3d", datetime!(2021-11-10 00:00 UTC));
+        archetype.platform = CloudPlatform::AWS;
+        archetype.image_id = "ami-11112222".to_string();
+        let mut instance = archived_image("eighty4-postgres-debian-11-1", "postgres",
+                                          "ami-11112222", datetime!(2021-11-10 01:00 UTC));
+        instance.platform = CloudPlatform::AWS;
+        instance.image_id = "ami-33334444".to_string();
+        let archived_images = vec!(archetype, instance);
+        let checks = source_checks(&archived_images, |_, _| Ok(Some("ami-0a1b2c3d".to_string()))).unwrap();
+        assert_eq!(checks[1].image.image_name, "eighty4-postgres-debian-11-1");
+        assert_eq!(checks[1].built_from, Some("ami-0a1b2c3d"));
+        assert_eq!(checks[1].status(), "current");
+    }
+
+    #[test]
+    fn test_source_checks_resolve_current_os_image_once_per_platform_and_os() {
+        let archived_images = archived_images();
+        let mut lookups = 0;
+        source_checks(&archived_images, |_, _| {
+            lookups += 1;
+            Ok(None)
+        }).unwrap();
+        assert_eq!(lookups, 1);
+    }
+
+    #[test]
+    fn test_source_check_unknown_without_exact_source_image() {
+        let mut image = archived_image("eighty4-archetype-debian-11-1", "archetype", "debian:11", datetime!(2021-11-10 00:00 UTC));
+        image.os = UBUNTU_2004;
+        image.source_image = UBUNTU_2004.to_string();
+        let archived_images = vec!(image);
+        let checks = source_checks(&archived_images, |_, _| Ok(Some("ubuntu-2004-focal-v20220110".to_string()))).unwrap();
+        assert_eq!(checks[0].status(), "unknown");
+        assert!(!checks[0].is_outdated());
+    }
+}
@@ -1,48 +1,305 @@
+use std::borrow::Cow;
+
+use time::OffsetDateTime;
+
+use crate::ansible::AnsiblePlaybook;
+use crate::api::binaries::PathLookup;
 use crate::api::context::SwoonContext;
 use crate::api::output::file::{Directory, File};
-use crate::api::output::file::Directory::GeneratedRoot;
+use crate::api::output::file::Directory::{AnsibleRoles, GeneratedRoot, ImageArchive};
 use crate::api::output::template::{Template, template_object};
+use crate::api::process::Process;
 use crate::api::task;
+use crate::images::{ARCHETYPE_LABEL, BakingPlan, image_name_prefix, ImageSource, ImageSpec, InstanceImagePlan};
+use crate::images::archive::{archive_image, ArchivedFiles, ArchivedImage, PackerManifest};
+use crate::images::inputs::{inputs_hash, PlanStatus};
+use crate::images::lifecycle::in_family;
+use crate::images::provenance::{GitState, Provenance};
+use crate::platforms::images::{self as platform_images, PlatformImage};
 pub use crate::platforms::packer::source;
 
+pub struct PackerPlugin {
+    pub name: Cow<'static, str>,
+    pub source: Cow<'static, str>,
+    pub version: Cow<'static, str>,
+}
+
+impl PackerPlugin {
+    pub const ANSIBLE: PackerPlugin = PackerPlugin {
+        name: Cow::Borrowed("ansible"),
+        source: Cow::Borrowed("github.com/hashicorp/ansible"),
+        version: Cow::Borrowed(">= 1.0.0"),
+    };
+}
+
 pub trait PackerSource {
     fn name(&self) -> String;
+    fn plugin(&self) -> PackerPlugin;
+    /// The exact image the source builds from, resolved from the OS image family where possible.
+    fn source_image(&self) -> String;
+    /// Stamps the image with provenance through the labels, tags or description the platform supports.
+    fn set_provenance(&mut self, provenance: &Provenance) -> task::Result<()>;
     fn to_hcl(&self) -> String;
 }
 
 pub struct PackerBuild {
-    // provisioning: PackerProvisioning,
+    name: String,
+    provisioning: PackerProvisioning,
     sources: Vec<Box<dyn PackerSource>>,
+    /// Specs of the images built by each source, in the same order as sources.
+    specs: Vec<ImageSpec>,
+    /// Hashes of the inputs of the images built by each source, in the same order as sources.
+    inputs_hashes: Vec<String>,
 }
 
 impl PackerBuild {
     pub fn archetype(sources: Vec<Box<dyn PackerSource>>) -> Self {
         Self {
-            // provisioning: PackerProvisioning::archetype(),
+            name: ARCHETYPE_LABEL.to_string(),
+            provisioning: PackerProvisioning::archetype(),
+            sources,
+            specs: Vec::new(),
+            inputs_hashes: Vec::new(),
+        }
+    }
+
+    pub fn instance(name: &str, playbook: &str, sources: Vec<Box<dyn PackerSource>>) -> Self {
+        Self {
+            name: name.to_string(),
+            provisioning: PackerProvisioning::instance(name, playbook),
+            sources,
+            specs: Vec::new(),
+            inputs_hashes: Vec::new(),
+        }
+    }
+
+    pub fn from_plan(ctx: &SwoonContext, baking_plan: &BakingPlan) -> task::Result<Self> {
+        let mut sources = Vec::new();
+        for image_spec in &baking_plan.archetypes {
+            sources.push(source::from_spec(ctx, image_spec)?);
+        }
+        Self {
+            specs: baking_plan.archetypes.clone(),
+            ..Self::archetype(sources)
+        }.with_inputs_hashes(ctx)
+    }
+
+    /// Builds the instance image from the archetype image family on platforms with image families
+    /// and resolves the latest archetype image to build from on other platforms.
+    pub fn from_instance_plan(ctx: &SwoonContext, instance_plan: &InstanceImagePlan) -> task::Result<Self> {
+        let archetype_prefix = instance_plan.archetype_name_prefix(ctx.config());
+        let mut sources = Vec::new();
+        let mut specs = Vec::new();
+        for platform in &instance_plan.platforms {
+            let provider = ctx.platforms.provider(platform)?;
+            let source = if provider.has_image_families() {
+                ImageSource::Family {
+                    name: instance_plan.archetype_family(ctx.config()),
+                    os: instance_plan.os.clone(),
+                }
+            } else {
+                match provider.latest_image(&archetype_prefix)? {
+                    Some(image) => ImageSource::Image { name: image, os: instance_plan.os.clone() },
+                    None => return task::Error::result(format!(
+                        "no {} archetype image on {} for instance image {}",
+                        instance_plan.os, platform.to_str(), instance_plan.name)),
+                }
+            };
+            let spec = ImageSpec {
+                label: instance_plan.name.clone(),
+                platform: platform.clone(),
+                source,
+                version: instance_plan.version.clone(),
+            };
+            sources.push(source::from_spec(ctx, &spec)?);
+            specs.push(spec);
+        }
+        Self {
+            specs,
+            ..Self::instance(&instance_plan.name, &instance_plan.playbook, sources)
+        }.with_inputs_hashes(ctx)
+    }
+
+    /// Hashes the inputs of each image, leaving out the image name that changes with every bake.
+    fn with_inputs_hashes(mut self, ctx: &SwoonContext) -> task::Result<Self> {
+        let mut inputs_hashes = Vec::new();
+        for (source, spec) in self.sources.iter().zip(&self.specs) {
+            let hcl = format!("{}\n\n{}", source.to_hcl(), self.render_hcl(&[source.as_ref()]));
+            inputs_hashes.push(inputs_hash(
+                &hcl.replace(&spec.image_name(ctx.config()), ""),
+                &source.source_image(),
+                self.provisioning.playbook.as_ref(),
+            )?);
+        }
+        self.inputs_hashes = inputs_hashes;
+        Ok(self)
+    }
+
+    pub fn specs(&self) -> &[ImageSpec] {
+        &self.specs
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+    }
+
+    /// Compares each image's inputs with the last archived bake of the image in the environment,
+    /// falling back to the inputs hash labels of the images of its family on the platform.
+    pub fn plan_statuses(&self,
+                         ctx: &SwoonContext,
+                         archived_images: &[ArchivedImage],
+                         environment: Option<&str>) -> task::Result<Vec<PlanStatus>> {
+        self.specs.iter().zip(&self.inputs_hashes)
+            .map(|(spec, inputs_hash)| {
+                let status = PlanStatus::of(
+                    archived_images, environment, &spec.platform, &spec.label, spec.os(), Some(inputs_hash));
+                if status == PlanStatus::Unchanged {
+                    return Ok(status);
+                }
+                let prefix = image_name_prefix(ctx.config(), &spec.label, spec.os());
+                Ok(match platform_images::images_by_prefix(ctx, &spec.platform, &prefix)? {
+                    None => status,
+                    Some(images) => {
+                        let family_images: Vec<PlatformImage> = images.into_iter()
+                            .filter(|image| in_family(&prefix, &image.name))
+                            .collect();
+                        status.or_labeled(&family_images, Some(inputs_hash))
+                    }
+                })
+            })
+            .collect()
+    }
+
+    /// Keeps the sources of the images that are selected for baking.
+    pub fn select(self, selected: &[bool]) -> Self {
+        let mut sources = Vec::new();
+        let mut specs = Vec::new();
+        let mut inputs_hashes = Vec::new();
+        let images = self.sources.into_iter().zip(self.specs).zip(self.inputs_hashes);
+        for (((source, spec), inputs_hash), select) in images.zip(selected) {
+            if *select {
+                sources.push(source);
+                specs.push(spec);
+                inputs_hashes.push(inputs_hash);
+            }
+        }
+        Self {
+            name: self.name,
+            provisioning: self.provisioning,
             sources,
+            specs,
+            inputs_hashes,
+        }
+    }
+
+    fn filename(&self) -> String {
+        format!("{}.pkr.hcl", self.name)
+    }
+
+    pub fn bake(&mut self, ctx: &SwoonContext, git: &Option<GitState>) -> task::Result<()> {
+        self.set_provenance(git)?;
+        self.write_config_files()?;
+        self.provisioning.stage()?;
+        self.invoke_packer(ctx, "init")?;
+        PackerManifest::clear()?;
+        let started_at = OffsetDateTime::now_utc();
+        self.invoke_packer(ctx, "build")?;
+        self.archive(ctx, started_at, OffsetDateTime::now_utc())
+    }
+
+    /// Provenance is set after hashing inputs so that the git commit does not change the inputs hash.
+    fn set_provenance(&mut self, git: &Option<GitState>) -> task::Result<()> {
+        for ((source, spec), inputs_hash) in self.sources.iter_mut().zip(&self.specs).zip(&self.inputs_hashes) {
+            let provenance = Provenance {
+                inputs_hash: inputs_hash.clone(),
+                git: git.clone(),
+                source_image: source.source_image(),
+                os: spec.os().clone(),
+            };
+            source.set_provenance(&provenance)?;
         }
+        task::SUCCESS
     }
 
-    pub fn default_archetype(ctx: &SwoonContext) -> Self {
-        let cfg = ctx.config();
-        Self::archetype(vec!(
-            source::from_os(ctx, &cfg.default_platform, &cfg.default_os)
-        ))
+    /// Records each image built by packer in the image archive.
+    fn archive(&self, ctx: &SwoonContext, started_at: OffsetDateTime, finished_at: OffsetDateTime) -> task::Result<()> {
+        let manifest = PackerManifest::read()?;
+        let files = ArchivedFiles {
+            hcl_filename: self.filename(),
+            playbook_filename: self.provisioning.playbook.as_ref().map(|p| p.filename()),
+            roles_dir: self.provisioning.playbook.as_ref().map(|_| AnsibleRoles.path()),
+        };
+        for ((source, spec), inputs_hash) in self.sources.iter().zip(&self.specs).zip(&self.inputs_hashes) {
+            let build = manifest.build(&source.name())?;
+            let image = ArchivedImage {
+                environment: ctx.environment().map(|environment| environment.name.clone()),
+                ..ArchivedImage::new(
+                    spec.image_name(ctx.config()),
+                    ctx.platforms.provider(&spec.platform)?.image_id_from_artifact(&build.artifact_id),
+                    Some(inputs_hash.clone()),
+                    source.source_image(),
+                    spec,
+                    started_at,
+                    finished_at,
+                )
+            };
+            archive_image(&image, &files)?;
+            ctx.write_line(format!("Archived {} to {}",
+                                   image.image_name,
+                                   ImageArchive.path().join(image.archive_path()).display()));
+        }
+        task::SUCCESS
     }
 
-    pub fn bake(&self) -> task::Result<()> {
-        // todo[bake-archetype] invoke packer
-        Ok(())
+    fn invoke_packer(&self, ctx: &SwoonContext, packer_cmd: &str) -> task::Result<()> {
+        let filename = self.filename();
+        ctx.write_line(format!("Running packer {} for {}", packer_cmd, filename));
+        let envs = if ctx.opts.debug {
+            vec!(("PACKER_LOG", "1"))
+        } else {
+            vec!()
+        };
+        let result = Process::stream_from_dir(
+            GeneratedRoot.path(),
+            &ctx.packer_path()?,
+            [packer_cmd, filename.as_str()],
+            envs,
+        );
+        match result {
+            Ok(()) => task::SUCCESS,
+            Err(e) => task::Error::result(format!("packer {} failed: {}", packer_cmd, e)),
+        }
     }
 
     pub(crate) fn to_hcl(&self) -> String {
-        let source = self.sources.get(0).unwrap().as_ref();
+        self.render_hcl(&self.sources.iter().map(|source| source.as_ref()).collect::<Vec<&dyn PackerSource>>())
+    }
+
+    fn render_hcl(&self, sources: &[&dyn PackerSource]) -> String {
+        let mut plugins = Vec::new();
+        sources.iter()
+            .map(|source| source.plugin())
+            .chain([PackerPlugin::ANSIBLE])
+            .for_each(|plugin| {
+                if !plugins.iter().any(|p: &PackerPlugin| p.name == plugin.name) {
+                    plugins.push(plugin);
+                }
+            });
+        let plugins = plugins.iter()
+            .map(|plugin| template_object!({
+                "name": plugin.name,
+                "source": plugin.source,
+                "version": plugin.version,
+            }))
+            .collect::<Vec<liquid::Object>>();
 
         let result = Template::render(
             include_bytes!("build.pkr.hcl.liquid"),
             &template_object!({
-                "source_name": source.name(), // todo[bake-archetype] handle sources as a list
-                "image_name": "archetype", // todo[bake-archetype] resolve image name
+                "plugins": plugins,
+                "source_names": sources.iter().map(|s| s.name()).collect::<Vec<String>>(),
+                "playbook_file": self.provisioning.playbook.as_ref().map(|p| p.filename()),
+                "roles_dir": AnsibleRoles.path().file_name().unwrap().to_str(),
             }),
         );
         match result {
@@ -56,26 +313,31 @@ impl PackerBuild {
     }
 }
 
-// pub struct PackerProvisioning {
-//     playbook: Option<AnsiblePlaybook>,
-//     startup: Option<String>,
-// }
-//
-// impl PackerProvisioning {
-//     pub fn default() -> Self {
-//         Self {
-//             playbook: None,
-//             startup: None,
-//         }
-//     }
-//
-//     pub fn archetype() -> Self {
-//         Self {
-//             playbook: Some(AnsiblePlaybook::archetype()),
-//             startup: None,
-//         }
-//     }
-// }
+pub struct PackerProvisioning {
+    playbook: Option<AnsiblePlaybook>,
+}
+
+impl PackerProvisioning {
+    pub fn archetype() -> Self {
+        Self {
+            playbook: Some(AnsiblePlaybook::archetype()),
+        }
+    }
+
+    pub fn instance(name: &str, playbook: &str) -> Self {
+        Self {
+            playbook: Some(AnsiblePlaybook::instance(name, playbook)),
+        }
+    }
+
+    /// Writes provisioning files to the paths referenced by the generated packer build.
+    fn stage(&self) -> task::Result<()> {
+        if let Some(playbook) = &self.playbook {
+            playbook.stage(GeneratedRoot)?;
+        }
+        task::SUCCESS
+    }
+}
 
 pub struct PackerBuildFile<'a> {
     build: &'a PackerBuild,
@@ -102,6 +364,62 @@ impl<'a> File for PackerBuildFile<'a> {
     }
 
     fn output_path(&self) -> (Directory, String) {
-        (GeneratedRoot, "archetype.pkr.hcl".to_string())
+        (GeneratedRoot, self.build.filename())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestSource {}
+
+    impl PackerSource for TestSource {
+        fn name(&self) -> String {
+            "source.test.archetype".to_string()
+        }
+
+        fn plugin(&self) -> PackerPlugin {
+            PackerPlugin {
+                name: "test".into(),
+                source: "github.com/eighty4/test".into(),
+                version: ">= 0.0.1".into(),
+            }
+        }
+
+        fn source_image(&self) -> String {
+            "debian-11-bullseye-v20211209".to_string()
+        }
+
+        fn set_provenance(&mut self, _: &Provenance) -> task::Result<()> {
+            task::SUCCESS
+        }
+
+        fn to_hcl(&self) -> String {
+            String::new()
+        }
+    }
+
+    #[test]
+    fn test_build_to_hcl_requires_source_and_ansible_plugins() {
+        let hcl = PackerBuild::archetype(vec!(Box::new(TestSource {}))).to_hcl();
+        assert!(hcl.contains("test = {\n            source  = \"github.com/eighty4/test\""));
+        assert!(hcl.contains("ansible = {\n            source  = \"github.com/hashicorp/ansible\""));
+        assert!(hcl.contains("\"source.test.archetype\","));
+    }
+
+    #[test]
+    fn test_build_to_hcl_lists_every_source() {
+        let hcl = PackerBuild::archetype(vec!(Box::new(TestSource {}), Box::new(TestSource {}))).to_hcl();
+        assert!(hcl.contains("sources = [\n        \"source.test.archetype\",\n        \"source.test.archetype\",\n    ]"));
+        assert_eq!(hcl.matches("test = {").count(), 1);
+    }
+
+    #[test]
+    fn test_build_to_hcl_provisions_playbook_with_roles_path() {
+        let hcl = PackerBuild::archetype(vec!(Box::new(TestSource {}))).to_hcl();
+        assert!(hcl.contains("playbook_file    = \"${path.root}/archetype.yml\""));
+        assert!(hcl.contains("ansible_env_vars = [\"ANSIBLE_ROLES_PATH=${path.root}/roles\"]"));
+        assert!(!hcl.contains("provisioner \"shell\""));
     }
 }
@@ -0,0 +1,251 @@
+use std::path::PathBuf;
+
+use crate::api::{OperatingSystem, task};
+use crate::api::process::Process;
+use crate::platforms::aws::images;
+use crate::platforms::images::PlatformImage;
+
+const ERR_DEFAULT_REGION: &str = r"aws does not have a configured default region
+
+run 'aws configure set region $my_region'";
+
+pub struct AwsCli {
+    path: PathBuf,
+}
+
+impl AwsCli {
+    pub fn new(path: PathBuf) -> Self {
+        AwsCli {
+            path,
+        }
+    }
+
+    pub async fn account_id(&self) -> task::Result<String> {
+        Process::invoke(&self.path, [
+            "sts",
+            "get-caller-identity",
+            "--query=Account",
+            "--output=text",
+            "--no-cli-pager",
+        ])
+    }
+
+    pub async fn default_region(&self) -> task::Result<String> {
+        let region = Process::invoke(&self.path, [
+            "configure",
+            "get",
+            "region",
+        ]).unwrap_or_default();
+        if region.is_empty() {
+            task::Error::result(ERR_DEFAULT_REGION)
+        } else {
+            Ok(region)
+        }
+    }
+
+    pub async fn image_id_by_os(&self, region: &str, os: &OperatingSystem) -> task::Result<String> {
+        let ami_filter = images::ami_filter_by_os(os);
+        let image_id = Process::invoke(&self.path, [
+            "ec2",
+            "describe-images",
+            format!("--region={}", region).as_ref(),
+            format!("--owners={}", ami_filter.owner).as_ref(),
+            "--filters",
+            format!("Name=name,Values={}", ami_filter.name).as_ref(),
+            "Name=architecture,Values=x86_64",
+            "Name=state,Values=available",
+            "--query=sort_by(Images, &CreationDate)[-1].ImageId",
+            "--output=text",
+            "--no-cli-pager",
+        ])?;
+        if image_id.is_empty() || image_id == "None" {
+            task::Error::result(format!("no ami found for {} in {}", os, region))
+        } else {
+            Ok(image_id)
+        }
+    }
+
+    pub async fn images_by_prefix(&self, region: &str, prefix: &str) -> task::Result<Vec<PlatformImage>> {
+        let output = Process::invoke(&self.path, [
+            "ec2",
+            "describe-images",
+            format!("--region={}", region).as_ref(),
+            "--owners=self",
+            "--filters",
+            format!("Name=name,Values={}*", prefix).as_ref(),
+            "--query=Images[].[Name,ImageId,CreationDate,State,'',Tags[?Key=='swoon-inputs-hash'].Value|[0]]",
+            "--output=text",
+            "--no-cli-pager",
+        ])?;
+        PlatformImage::parse_lines(&output)
+    }
+
+    /// IDs of the EBS snapshots backing an AMI, which outlive the AMI when it is deregistered.
+    pub async fn snapshot_ids(&self, region: &str, image_id: &str) -> task::Result<Vec<String>> {
+        let output = Process::invoke(&self.path, Self::snapshot_ids_args(region, image_id))?;
+        Ok(output.split_whitespace()
+            .filter(|snapshot_id| *snapshot_id != "None")
+            .map(|snapshot_id| snapshot_id.to_string())
+            .collect())
+    }
+
+    fn snapshot_ids_args(region: &str, image_id: &str) -> Vec<String> {
+        vec!(
+            "ec2".to_string(),
+            "describe-images".to_string(),
+            format!("--region={}", region),
+            format!("--image-ids={}", image_id),
+            "--query=Images[].BlockDeviceMappings[].Ebs.SnapshotId".to_string(),
+            "--output=text".to_string(),
+            "--no-cli-pager".to_string(),
+        )
+    }
+
+    /// Deregisters the AMI, leaving its EBS snapshots to be deleted with delete_snapshot.
+    pub async fn deregister_image(&self, region: &str, image_id: &str) -> task::Result<()> {
+        Process::invoke(&self.path, Self::deregister_image_args(region, image_id))?;
+        task::SUCCESS
+    }
+
+    fn deregister_image_args(region: &str, image_id: &str) -> Vec<String> {
+        vec!(
+            "ec2".to_string(),
+            "deregister-image".to_string(),
+            format!("--region={}", region),
+            format!("--image-id={}", image_id),
+            "--no-cli-pager".to_string(),
+        )
+    }
+
+    pub async fn delete_snapshot(&self, region: &str, snapshot_id: &str) -> task::Result<()> {
+        Process::invoke(&self.path, Self::delete_snapshot_args(region, snapshot_id))?;
+        task::SUCCESS
+    }
+
+    fn delete_snapshot_args(region: &str, snapshot_id: &str) -> Vec<String> {
+        vec!(
+            "ec2".to_string(),
+            "delete-snapshot".to_string(),
+            format!("--region={}", region),
+            format!("--snapshot-id={}", snapshot_id),
+            "--no-cli-pager".to_string(),
+        )
+    }
+
+    /// Starts copying an image to a region and returns the id of the copy.
+    pub async fn copy_image(&self,
+                            source_region: &str,
+                            source_image_id: &str,
+                            region: &str,
+                            image_name: &str) -> task::Result<String> {
+        Process::invoke(&self.path, [
+            "ec2",
+            "copy-image",
+            format!("--source-region={}", source_region).as_ref(),
+            format!("--source-image-id={}", source_image_id).as_ref(),
+            format!("--region={}", region).as_ref(),
+            format!("--name={}", image_name).as_ref(),
+            "--query=ImageId",
+            "--output=text",
+            "--no-cli-pager",
+        ])
+    }
+
+    pub async fn wait_for_image(&self, region: &str, image_id: &str) -> task::Result<()> {
+        Process::invoke(&self.path, [
+            "ec2",
+            "wait",
+            "image-available",
+            format!("--region={}", region).as_ref(),
+            format!("--image-ids={}", image_id).as_ref(),
+            "--no-cli-pager",
+        ])?;
+        task::SUCCESS
+    }
+
+    /// Tags of an image, read to copy them onto promoted images.
+    pub async fn image_tags(&self, region: &str, image_id: &str) -> task::Result<Vec<(String, String)>> {
+        let output = Process::invoke(&self.path, [
+            "ec2",
+            "describe-images",
+            format!("--region={}", region).as_ref(),
+            format!("--image-ids={}", image_id).as_ref(),
+            "--query=Images[0].Tags",
+            "--output=json",
+            "--no-cli-pager",
+        ])?;
+        let tags: serde_json::Value = serde_json::from_str(&output)?;
+        Ok(tags.as_array().into_iter().flatten()
+            .map(|tag| (tag["Key"].as_str().unwrap_or_default().to_string(),
+                        tag["Value"].as_str().unwrap_or_default().to_string()))
+            .collect())
+    }
+
+    pub async fn tag_image(&self, region: &str, image_id: &str, tags: &[(String, String)]) -> task::Result<()> {
+        Process::invoke(&self.path, Self::tag_image_args(region, image_id, tags))?;
+        task::SUCCESS
+    }
+
+    fn tag_image_args(region: &str, image_id: &str, tags: &[(String, String)]) -> Vec<String> {
+        let mut args = vec!(
+            "ec2".to_string(),
+            "create-tags".to_string(),
+            format!("--region={}", region),
+            format!("--resources={}", image_id),
+            "--tags".to_string(),
+        );
+        args.extend(tags.iter().map(|(key, value)| format!("Key={},Value={}", key, value)));
+        args.push("--no-cli-pager".to_string());
+        args
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tag_image_args() {
+        let tags = vec!(
+            ("swoon-inputs-hash".to_string(), "abc".to_string()),
+            ("swoon-promoted-from".to_string(), "eighty4-archetype-debian-11-1".to_string()),
+        );
+        assert_eq!(AwsCli::tag_image_args("us-west-2", "ami-0123456789", &tags), vec!(
+            "ec2",
+            "create-tags",
+            "--region=us-west-2",
+            "--resources=ami-0123456789",
+            "--tags",
+            "Key=swoon-inputs-hash,Value=abc",
+            "Key=swoon-promoted-from,Value=eighty4-archetype-debian-11-1",
+            "--no-cli-pager",
+        ));
+    }
+
+    #[test]
+    fn test_delete_image_args() {
+        assert_eq!(AwsCli::snapshot_ids_args("us-east-1", "ami-0123456789"), vec!(
+            "ec2",
+            "describe-images",
+            "--region=us-east-1",
+            "--image-ids=ami-0123456789",
+            "--query=Images[].BlockDeviceMappings[].Ebs.SnapshotId",
+            "--output=text",
+            "--no-cli-pager",
+        ));
+        assert_eq!(AwsCli::deregister_image_args("us-east-1", "ami-0123456789"), vec!(
+            "ec2",
+            "deregister-image",
+            "--region=us-east-1",
+            "--image-id=ami-0123456789",
+            "--no-cli-pager",
+        ));
+        assert_eq!(AwsCli::delete_snapshot_args("us-east-1", "snap-0123456789"), vec!(
+            "ec2",
+            "delete-snapshot",
+            "--region=us-east-1",
+            "--snapshot-id=snap-0123456789",
+            "--no-cli-pager",
+        ));
+    }
+}
@@ -0,0 +1,107 @@
+use crate::api::{OperatingSystem, task};
+
+const CANONICAL_OWNER_ID: &str = "099720109477";
+const DEBIAN_OWNER_ID: &str = "136693071363";
+const DEBIAN_LEGACY_OWNER_ID: &str = "379101102735";
+
+pub struct AmiFilter {
+    pub owner: &'static str,
+    pub name: String,
+}
+
+pub fn ami_filter_by_os(os: &OperatingSystem) -> AmiFilter {
+    match os {
+        OperatingSystem::Debian { version: 9 } => AmiFilter {
+            owner: DEBIAN_LEGACY_OWNER_ID,
+            name: "debian-stretch-hvm-x86_64-gp2-*".to_string(),
+        },
+        OperatingSystem::Debian { version } => AmiFilter {
+            owner: DEBIAN_OWNER_ID,
+            name: format!("debian-{}-amd64-*", version),
+        },
+        OperatingSystem::Ubuntu { version, minimal } => {
+            let release = format!("{}-{:02}.{:02}", ubuntu_codename(os), version.major, version.minor);
+            AmiFilter {
+                owner: CANONICAL_OWNER_ID,
+                name: if *minimal {
+                    format!("ubuntu-minimal/images/hvm-ssd/ubuntu-{}-amd64-minimal-*", release)
+                } else {
+                    format!("ubuntu/images/hvm-ssd/ubuntu-{}-amd64-server-*", release)
+                },
+            }
+        }
+    }
+}
+
+pub fn ssh_username_by_os(os: &OperatingSystem) -> &'static str {
+    match os {
+        OperatingSystem::Debian { .. } => "admin",
+        OperatingSystem::Ubuntu { .. } => "ubuntu",
+    }
+}
+
+fn ubuntu_codename(os: &OperatingSystem) -> &'static str {
+    match os {
+        OperatingSystem::Ubuntu { version, .. } if version.major == 18 => "bionic",
+        _ => "focal",
+    }
+}
+
+/// AMI names are 3 to 128 characters of letters, digits and the characters `()[]./-'@_` or spaces.
+pub fn validate_image_name(name: &str) -> task::Result<()> {
+    let valid = (3..=128).contains(&name.len())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || "()[]./-'@_ ".contains(c));
+    if valid {
+        task::SUCCESS
+    } else {
+        task::Error::result(format!("{} is not a valid AWS AMI name", name))
+    }
+}
+
+/// Tag values are at most 256 letters, digits, spaces and the characters `_.:/=+-@`.
+pub fn sanitize_tag_value(value: &str) -> String {
+    value.chars()
+        .map(|c| if c.is_alphanumeric() || " _.:/=+-@".contains(c) { c } else { '-' })
+        .take(256)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::{DEBIAN_11, DEBIAN_9, UBUNTU_1804_MINIMAL, UBUNTU_2004};
+
+    use super::*;
+
+    #[test]
+    fn test_sanitize_tag_value() {
+        assert_eq!(sanitize_tag_value("debian-11-amd64-20220121-894"), "debian-11-amd64-20220121-894");
+        assert_eq!(sanitize_tag_value("ubuntu:20.04"), "ubuntu:20.04");
+        assert_eq!(sanitize_tag_value("a\"b"), "a-b");
+    }
+
+    #[test]
+    fn test_ami_filter_by_os() {
+        let debian = ami_filter_by_os(&DEBIAN_11);
+        assert_eq!(debian.owner, DEBIAN_OWNER_ID);
+        assert_eq!(debian.name, "debian-11-amd64-*");
+
+        let stretch = ami_filter_by_os(&DEBIAN_9);
+        assert_eq!(stretch.owner, DEBIAN_LEGACY_OWNER_ID);
+        assert_eq!(stretch.name, "debian-stretch-hvm-x86_64-gp2-*");
+
+        let focal = ami_filter_by_os(&UBUNTU_2004);
+        assert_eq!(focal.owner, CANONICAL_OWNER_ID);
+        assert_eq!(focal.name, "ubuntu/images/hvm-ssd/ubuntu-focal-20.04-amd64-server-*");
+
+        let bionic_minimal = ami_filter_by_os(&UBUNTU_1804_MINIMAL);
+        assert_eq!(bionic_minimal.name, "ubuntu-minimal/images/hvm-ssd/ubuntu-bionic-18.04-amd64-minimal-*");
+    }
+
+    #[test]
+    fn test_validate_image_name() {
+        assert!(validate_image_name("eighty4-archetype-debian-11-20220104153012").is_ok());
+        assert!(validate_image_name("ab").is_err());
+        assert!(validate_image_name("eighty4:archetype").is_err());
+        assert!(validate_image_name(&"a".repeat(129)).is_err());
+    }
+}
@@ -0,0 +1,52 @@
+use std::path::PathBuf;
+
+use futures::join;
+
+use crate::api::task;
+use crate::api::config::SwoonConfig;
+use crate::platforms::aws::cli::AwsCli;
+
+pub mod cli;
+pub mod images;
+pub mod packer;
+pub mod provider;
+
+#[derive(Clone)]
+pub struct AwsContext {
+    pub account_id: String,
+    pub region: String,
+}
+
+impl AwsContext {
+    pub async fn init(path: PathBuf, cfg: &SwoonConfig) -> task::Result<Self> {
+        let aws_cli = AwsCli::new(path);
+        let (
+            account_id_result,
+            region_result,
+        ) = join!(
+            aws_cli.account_id(),
+            aws_cli.default_region(),
+        );
+
+        // the caller identity lookup fails when the aws cli is not authed
+        let account_id = account_id_result?;
+
+        let environment = cfg.environment.as_ref();
+        if let Some((name, account)) = environment.and_then(|e| e.account.as_ref().map(|a| (&e.name, a))) {
+            if &account_id != account {
+                return task::Error::result(format!(
+                    "aws cli is authed to account {} and environment {} is in account {}", account_id, name, account));
+            }
+        }
+
+        let region = match environment.and_then(|e| e.region.clone()) {
+            Some(region) => region,
+            None => region_result?,
+        };
+
+        Ok(Self {
+            account_id,
+            region,
+        })
+    }
+}
@@ -0,0 +1,82 @@
+use futures::executor;
+
+use crate::api::config::SwoonConfig;
+use crate::api::task;
+use crate::api::output::template::{Template, template_object};
+use crate::images::{ImageSource, ImageSpec};
+use crate::images::provenance::Provenance;
+use crate::packer::{PackerPlugin, PackerSource, source};
+use crate::platforms::aws::{AwsContext, images};
+use crate::platforms::aws::cli::AwsCli;
+
+pub struct AwsPackerSource {
+    image_name: String,
+    region: String,
+    source_ami: String,
+    source_label: String,
+    ssh_username: String,
+    tags: Vec<(String, String)>,
+}
+
+impl AwsPackerSource {
+    pub fn from_spec(cfg: &SwoonConfig,
+                     aws_ctx: &AwsContext,
+                     aws_cli: AwsCli,
+                     spec: &ImageSpec) -> task::Result<Box<Self>> {
+        let source_ami = match &spec.source {
+            ImageSource::OperatingSystem { os } => executor::block_on(
+                aws_cli.image_id_by_os(&aws_ctx.region, os))?,
+            ImageSource::Image { name, .. } => name.clone(),
+            ImageSource::Family { .. } => return source::unsupported_image_source(spec),
+        };
+        Ok(Box::from(Self {
+            image_name: spec.image_name(cfg),
+            region: aws_ctx.region.clone(),
+            source_ami,
+            source_label: spec.source_label(),
+            ssh_username: images::ssh_username_by_os(spec.os()).to_string(),
+            tags: Vec::new(),
+        }))
+    }
+}
+
+impl PackerSource for AwsPackerSource {
+    fn name(&self) -> String {
+        format!("source.amazon-ebs.{}", self.source_label)
+    }
+
+    fn plugin(&self) -> PackerPlugin {
+        PackerPlugin {
+            name: "amazon".into(),
+            source: "github.com/hashicorp/amazon".into(),
+            version: ">= 1.0.0".into(),
+        }
+    }
+
+    fn source_image(&self) -> String {
+        self.source_ami.clone()
+    }
+
+    fn set_provenance(&mut self, provenance: &Provenance) -> task::Result<()> {
+        self.tags = provenance.sanitized_labels(images::sanitize_tag_value);
+        task::SUCCESS
+    }
+
+    fn to_hcl(&self) -> String {
+        let result = Template::render(
+            include_bytes!("source.pkr.hcl.liquid"),
+            &template_object!({
+                "region": self.region,
+                "image_name": self.image_name,
+                "source_ami": self.source_ami,
+                "source_label": self.source_label,
+                "ssh_username": self.ssh_username,
+                "tags": self.tags,
+            }),
+        );
+        match result {
+            Ok(s) => s,
+            Err(e) => e.exit(),
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use std::path::PathBuf;
+
+use futures::executor;
+
+use crate::api::{CloudPlatform, OperatingSystem, task};
+use crate::api::binaries::{BinaryPaths, PathLookup};
+use crate::api::config::{EnvironmentConfig, SwoonConfig};
+use crate::images::archive::ArchivedImage;
+use crate::images::ImageSpec;
+use crate::packer::PackerSource;
+use crate::platforms::aws::cli::AwsCli;
+use crate::platforms::aws::{AwsContext, images};
+use crate::platforms::aws::packer::AwsPackerSource;
+use crate::platforms::images::{latest_in_family, PlatformImage, promoted_labels, regional_image_id};
+use crate::platforms::provider::PlatformProvider;
+
+pub struct AwsProvider {
+    aws_ctx: AwsContext,
+    aws_path: PathBuf,
+}
+
+impl AwsProvider {
+    pub fn new(aws_ctx: AwsContext, aws_path: PathBuf) -> Self {
+        Self {
+            aws_ctx,
+            aws_path,
+        }
+    }
+
+    fn aws_cli(&self) -> AwsCli {
+        AwsCli::new(self.aws_path.clone())
+    }
+}
+
+impl PlatformProvider for AwsProvider {
+    fn init(binary_paths: &BinaryPaths, cfg: &SwoonConfig, _: &CloudPlatform) -> task::Result<Self> {
+        let aws_path = binary_paths.aws_path()?;
+        let aws_ctx = executor::block_on(AwsContext::init(aws_path.clone(), cfg))?;
+        Ok(Self::new(aws_ctx, aws_path))
+    }
+
+    fn supports_instance_images() -> bool {
+        true
+    }
+
+    fn platform(&self) -> CloudPlatform {
+        CloudPlatform::AWS
+    }
+
+    fn validate_image_name(&self, name: &str) -> task::Result<()> {
+        images::validate_image_name(name)
+    }
+
+    fn image_id_from_artifact(&self, artifact_id: &str) -> String {
+        regional_image_id(artifact_id)
+    }
+
+    fn current_os_image(&self, os: &OperatingSystem) -> task::Result<Option<String>> {
+        Ok(Some(executor::block_on(self.aws_cli().image_id_by_os(&self.aws_ctx.region, os))?))
+    }
+
+    fn packer_source(&self, cfg: &SwoonConfig, spec: &ImageSpec) -> task::Result<Box<dyn PackerSource>> {
+        Ok(AwsPackerSource::from_spec(cfg, &self.aws_ctx, self.aws_cli(), spec)?)
+    }
+
+    fn images_by_prefix(&self, prefix: &str) -> task::Result<Option<Vec<PlatformImage>>> {
+        Ok(Some(executor::block_on(self.aws_cli().images_by_prefix(&self.aws_ctx.region, prefix))?))
+    }
+
+    /// AMIs are looked up by id, since instances are launched from the id rather than the name.
+    fn latest_image(&self, prefix: &str) -> task::Result<Option<String>> {
+        let images = executor::block_on(self.aws_cli().images_by_prefix(&self.aws_ctx.region, prefix))?;
+        Ok(latest_in_family(prefix, images).map(|image| image.id))
+    }
+
+    /// Deregisters the AMI and deletes the EBS snapshots backing it, which would otherwise
+    /// keep being billed after the AMI is gone.
+    fn delete_image(&self, image: &PlatformImage) -> task::Result<()> {
+        let aws_cli = self.aws_cli();
+        let snapshot_ids = executor::block_on(aws_cli.snapshot_ids(&self.aws_ctx.region, &image.id))?;
+        executor::block_on(aws_cli.deregister_image(&self.aws_ctx.region, &image.id))?;
+        for snapshot_id in snapshot_ids {
+            executor::block_on(aws_cli.delete_snapshot(&self.aws_ctx.region, &snapshot_id))?;
+        }
+        task::SUCCESS
+    }
+
+    fn promote_image(&self,
+                     source: &ArchivedImage,
+                     image_name: &str,
+                     environment: &EnvironmentConfig) -> task::Result<String> {
+        let aws_cli = self.aws_cli();
+        if let Some(account) = environment.account.as_ref().filter(|account| *account != &self.aws_ctx.account_id) {
+            return task::Error::result(format!(
+                "aws cli is authed to account {} and environment {} is in account {}",
+                self.aws_ctx.account_id, environment.name, account));
+        }
+        let region = match &environment.region {
+            Some(region) => region.clone(),
+            None => executor::block_on(aws_cli.default_region())?,
+        };
+        let source_tags = executor::block_on(aws_cli.image_tags(&self.aws_ctx.region, &source.image_id))?;
+        let image_id = executor::block_on(aws_cli.copy_image(&self.aws_ctx.region, &source.image_id, &region, image_name))?;
+        executor::block_on(aws_cli.wait_for_image(&region, &image_id))?;
+        executor::block_on(aws_cli.tag_image(&region, &image_id, &promoted_labels(source_tags, &source.image_name)))?;
+        Ok(image_id)
+    }
+}
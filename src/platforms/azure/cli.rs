@@ -0,0 +1,66 @@
+use std::path::PathBuf;
+
+use crate::api::task;
+use crate::api::process::Process;
+
+const ERR_DEFAULT_GROUP: &str = r"az does not have a configured default resource group
+
+run 'az config set defaults.group=$my_resource_group'";
+
+const ERR_DEFAULT_LOCATION: &str = r"az does not have a configured default location
+
+run 'az config set defaults.location=$my_location'";
+
+pub struct AzureCli {
+    path: PathBuf,
+}
+
+impl AzureCli {
+    pub fn new(path: PathBuf) -> Self {
+        AzureCli {
+            path,
+        }
+    }
+
+    pub async fn subscription_id(&self) -> task::Result<String> {
+        Process::invoke(&self.path, [
+            "account",
+            "show",
+            "--query=id",
+            "--output=tsv",
+        ])
+    }
+
+    pub async fn default_resource_group(&self) -> task::Result<String> {
+        self.config_default("group", ERR_DEFAULT_GROUP)
+    }
+
+    pub async fn default_location(&self) -> task::Result<String> {
+        self.config_default("location", ERR_DEFAULT_LOCATION)
+    }
+
+    pub async fn resource_group_exists(&self, resource_group: &str) -> task::Result<bool> {
+        let exists = Process::invoke(&self.path, [
+            "group",
+            "exists",
+            format!("--name={}", resource_group).as_ref(),
+            "--output=tsv",
+        ])?;
+        Ok(exists == "true")
+    }
+
+    fn config_default(&self, key: &str, err_msg: &str) -> task::Result<String> {
+        let value = Process::invoke(&self.path, [
+            "config",
+            "get",
+            format!("defaults.{}", key).as_ref(),
+            "--query=value",
+            "--output=tsv",
+        ]).unwrap_or_default();
+        if value.is_empty() {
+            task::Error::result(err_msg)
+        } else {
+            Ok(value)
+        }
+    }
+}
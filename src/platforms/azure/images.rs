@@ -0,0 +1,84 @@
+use crate::api::{OperatingSystem, task};
+
+pub struct MarketplaceImage {
+    pub publisher: &'static str,
+    pub offer: String,
+    pub sku: String,
+}
+
+pub fn marketplace_image_by_os(os: &OperatingSystem) -> MarketplaceImage {
+    match os {
+        OperatingSystem::Debian { version: 9 } => MarketplaceImage {
+            publisher: "credativ",
+            offer: "Debian".to_string(),
+            sku: "9".to_string(),
+        },
+        OperatingSystem::Debian { version } => MarketplaceImage {
+            publisher: "Debian",
+            offer: format!("debian-{}", version),
+            sku: version.to_string(),
+        },
+        OperatingSystem::Ubuntu { version, minimal } if version.major == 18 && !minimal => MarketplaceImage {
+            publisher: "Canonical",
+            offer: "UbuntuServer".to_string(),
+            sku: "18.04-LTS".to_string(),
+        },
+        OperatingSystem::Ubuntu { version, minimal } => {
+            let codename = if version.major == 18 { "bionic" } else { "focal" };
+            let release = format!("{:02}_{:02}-lts", version.major, version.minor);
+            if *minimal {
+                MarketplaceImage {
+                    publisher: "Canonical",
+                    offer: format!("0001-com-ubuntu-minimal-{}", codename),
+                    sku: format!("minimal-{}", release),
+                }
+            } else {
+                MarketplaceImage {
+                    publisher: "Canonical",
+                    offer: format!("0001-com-ubuntu-server-{}", codename),
+                    sku: release,
+                }
+            }
+        }
+    }
+}
+
+/// Tag values are at most 256 characters.
+pub fn sanitize_tag_value(value: &str) -> String {
+    value.chars().take(256).collect()
+}
+
+/// Managed image names are at most 80 letters, digits, underscores, periods and hyphens,
+/// starting with a letter or digit and ending with a letter, digit or underscore.
+pub fn validate_image_name(name: &str) -> task::Result<()> {
+    let valid = name.len() <= 80
+        && name.starts_with(|c: char| c.is_ascii_alphanumeric())
+        && name.ends_with(|c: char| c.is_ascii_alphanumeric() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || "_.-".contains(c));
+    if valid {
+        task::SUCCESS
+    } else {
+        task::Error::result(format!("{} is not a valid Azure managed image name", name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::{DEBIAN_11, DEBIAN_9, UBUNTU_1804, UBUNTU_2004, UBUNTU_2004_MINIMAL};
+
+    use super::*;
+
+    fn urn(os: &OperatingSystem) -> String {
+        let image = marketplace_image_by_os(os);
+        format!("{}:{}:{}", image.publisher, image.offer, image.sku)
+    }
+
+    #[test]
+    fn test_marketplace_image_by_os() {
+        assert_eq!(urn(&DEBIAN_11), "Debian:debian-11:11");
+        assert_eq!(urn(&DEBIAN_9), "credativ:Debian:9");
+        assert_eq!(urn(&UBUNTU_2004), "Canonical:0001-com-ubuntu-server-focal:20_04-lts");
+        assert_eq!(urn(&UBUNTU_2004_MINIMAL), "Canonical:0001-com-ubuntu-minimal-focal:minimal-20_04-lts");
+        assert_eq!(urn(&UBUNTU_1804), "Canonical:UbuntuServer:18.04-LTS");
+    }
+}
@@ -0,0 +1,53 @@
+use std::path::PathBuf;
+
+use futures::join;
+
+use crate::api::task;
+use crate::api::config::SwoonConfig;
+use crate::platforms::azure::cli::AzureCli;
+
+pub mod cli;
+pub mod images;
+pub mod packer;
+pub mod provider;
+
+#[derive(Clone)]
+pub struct AzureContext {
+    pub location: String,
+    pub resource_group: String,
+    pub subscription_id: String,
+}
+
+impl AzureContext {
+    pub async fn init(path: PathBuf, cfg: &SwoonConfig) -> task::Result<Self> {
+        let azure_cli = AzureCli::new(path);
+        let (
+            subscription_id_result,
+            resource_group_result,
+            location_result,
+        ) = join!(
+            azure_cli.subscription_id(),
+            azure_cli.default_resource_group(),
+            azure_cli.default_location(),
+        );
+
+        let subscription_id = subscription_id_result?;
+        let resource_group = resource_group_result?;
+
+        if !azure_cli.resource_group_exists(&resource_group).await? {
+            return task::Error::result(format!(
+                "{} is not a resource group in azure subscription {}", resource_group, subscription_id));
+        }
+
+        let location = match cfg.environment.as_ref().and_then(|e| e.region.clone()) {
+            Some(location) => location,
+            None => location_result?,
+        };
+
+        Ok(Self {
+            location,
+            resource_group,
+            subscription_id,
+        })
+    }
+}
@@ -0,0 +1,82 @@
+use crate::api::config::SwoonConfig;
+use crate::api::task;
+use crate::api::output::template::{Template, template_object};
+use crate::images::{ImageSource, ImageSpec};
+use crate::images::provenance::Provenance;
+use crate::packer::{PackerPlugin, PackerSource, source};
+use crate::platforms::azure::{AzureContext, images};
+use crate::platforms::azure::images::MarketplaceImage;
+
+pub struct AzurePackerSource {
+    image_name: String,
+    location: String,
+    resource_group: String,
+    source_image: MarketplaceImage,
+    source_label: String,
+    subscription_id: String,
+    tags: Vec<(String, String)>,
+}
+
+impl AzurePackerSource {
+    pub fn from_spec(cfg: &SwoonConfig,
+                     azure_ctx: &AzureContext,
+                     spec: &ImageSpec) -> task::Result<Box<Self>> {
+        let os = match &spec.source {
+            ImageSource::OperatingSystem { os } => os,
+            ImageSource::Image { .. } | ImageSource::Family { .. } => return source::unsupported_image_source(spec),
+        };
+        Ok(Box::from(Self {
+            image_name: spec.image_name(cfg),
+            location: azure_ctx.location.clone(),
+            resource_group: azure_ctx.resource_group.clone(),
+            source_image: images::marketplace_image_by_os(os),
+            source_label: spec.source_label(),
+            subscription_id: azure_ctx.subscription_id.clone(),
+            tags: Vec::new(),
+        }))
+    }
+}
+
+impl PackerSource for AzurePackerSource {
+    fn name(&self) -> String {
+        format!("source.azure-arm.{}", self.source_label)
+    }
+
+    fn plugin(&self) -> PackerPlugin {
+        PackerPlugin {
+            name: "azure".into(),
+            source: "github.com/hashicorp/azure".into(),
+            version: ">= 1.0.0".into(),
+        }
+    }
+
+    fn source_image(&self) -> String {
+        format!("{}:{}:{}:latest", self.source_image.publisher, self.source_image.offer, self.source_image.sku)
+    }
+
+    fn set_provenance(&mut self, provenance: &Provenance) -> task::Result<()> {
+        self.tags = provenance.sanitized_labels(images::sanitize_tag_value);
+        task::SUCCESS
+    }
+
+    fn to_hcl(&self) -> String {
+        let result = Template::render(
+            include_bytes!("source.pkr.hcl.liquid"),
+            &template_object!({
+                "subscription_id": self.subscription_id,
+                "image_name": self.image_name,
+                "resource_group": self.resource_group,
+                "location": self.location,
+                "image_publisher": self.source_image.publisher,
+                "image_offer": self.source_image.offer,
+                "image_sku": self.source_image.sku,
+                "source_label": self.source_label,
+                "tags": self.tags,
+            }),
+        );
+        match result {
+            Ok(s) => s,
+            Err(e) => e.exit(),
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use futures::executor;
+
+use crate::api::{CloudPlatform, task};
+use crate::api::binaries::{BinaryPaths, PathLookup};
+use crate::api::config::SwoonConfig;
+use crate::images::ImageSpec;
+use crate::packer::PackerSource;
+use crate::platforms::azure::{AzureContext, images};
+use crate::platforms::azure::packer::AzurePackerSource;
+use crate::platforms::provider::PlatformProvider;
+
+pub struct AzureProvider {
+    azure_ctx: AzureContext,
+}
+
+impl PlatformProvider for AzureProvider {
+    fn init(binary_paths: &BinaryPaths, cfg: &SwoonConfig, _: &CloudPlatform) -> task::Result<Self> {
+        Ok(Self {
+            azure_ctx: executor::block_on(AzureContext::init(binary_paths.azure_path()?, cfg))?,
+        })
+    }
+
+    fn platform(&self) -> CloudPlatform {
+        CloudPlatform::Azure
+    }
+
+    fn validate_image_name(&self, name: &str) -> task::Result<()> {
+        images::validate_image_name(name)
+    }
+
+    fn packer_source(&self, cfg: &SwoonConfig, spec: &ImageSpec) -> task::Result<Box<dyn PackerSource>> {
+        Ok(AzurePackerSource::from_spec(cfg, &self.azure_ctx, spec)?)
+    }
+}
@@ -0,0 +1,41 @@
+use std::path::PathBuf;
+
+use crate::api::task;
+use crate::api::process::Process;
+use crate::api::util::split_string;
+
+const ERR_AUTH_CONTEXT: &str = r"doctl does not have a current auth context
+
+run 'doctl auth init'";
+
+pub struct DoctlCli {
+    path: PathBuf,
+}
+
+impl DoctlCli {
+    pub fn new(path: PathBuf) -> Self {
+        DoctlCli {
+            path,
+        }
+    }
+
+    pub async fn current_auth_context(&self) -> task::Result<String> {
+        let output = Process::invoke(&self.path, [
+            "auth",
+            "list",
+        ])?;
+        split_string("\n", output).iter()
+            .find(|auth_context| auth_context.ends_with("(current)"))
+            .map(|auth_context| auth_context.trim_end_matches("(current)").trim().to_string())
+            .map_or(task::Error::result(ERR_AUTH_CONTEXT), Ok)
+    }
+
+    pub async fn account_status(&self) -> task::Result<String> {
+        Process::invoke(&self.path, [
+            "account",
+            "get",
+            "--format=Status",
+            "--no-header",
+        ])
+    }
+}
@@ -0,0 +1,42 @@
+use crate::api::{OperatingSystem, task};
+
+pub fn image_slug_by_os(os: &OperatingSystem) -> task::Result<String> {
+    match os {
+        OperatingSystem::Debian { version } => Ok(format!("debian-{}-x64", version)),
+        OperatingSystem::Ubuntu { minimal: true, .. } => task::Error::result(format!(
+            "{} is not available as a DigitalOcean image", os)),
+        OperatingSystem::Ubuntu { version, .. } => Ok(format!(
+            "ubuntu-{}-{:02}-x64", version.major, version.minor)),
+    }
+}
+
+/// Tags are a single string of at most 255 letters, digits, colons, hyphens and underscores,
+/// so labels are joined to their value with a colon.
+pub fn tag(name: &str, value: &str) -> String {
+    format!("{}:{}", name, value).chars()
+        .map(|c| if c.is_ascii_alphanumeric() || ":-_".contains(c) { c } else { '-' })
+        .take(255)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::{DEBIAN_11, DEBIAN_9, UBUNTU_1804, UBUNTU_2004, UBUNTU_2004_MINIMAL};
+
+    use super::*;
+
+    #[test]
+    fn test_image_slug_by_os() {
+        assert_eq!(image_slug_by_os(&DEBIAN_11), Ok("debian-11-x64".to_string()));
+        assert_eq!(image_slug_by_os(&DEBIAN_9), Ok("debian-9-x64".to_string()));
+        assert_eq!(image_slug_by_os(&UBUNTU_2004), Ok("ubuntu-20-04-x64".to_string()));
+        assert_eq!(image_slug_by_os(&UBUNTU_1804), Ok("ubuntu-18-04-x64".to_string()));
+        assert!(image_slug_by_os(&UBUNTU_2004_MINIMAL).is_err());
+    }
+
+    #[test]
+    fn test_tag() {
+        assert_eq!(tag("swoon-os", "debian:11"), "swoon-os:debian:11");
+        assert_eq!(tag("swoon-version", "0.0.1"), "swoon-version:0-0-1");
+    }
+}
@@ -0,0 +1,82 @@
+use std::env;
+use std::path::PathBuf;
+
+use futures::join;
+
+use crate::api::task;
+use crate::api::config::SwoonConfig;
+use crate::api::yaml::{ConfigErrors, YamlNode};
+use crate::platforms::digitalocean::cli::DoctlCli;
+
+pub mod cli;
+pub mod images;
+pub mod packer;
+pub mod provider;
+
+const ERR_API_TOKEN: &str = r"packer requires a DigitalOcean api token to create snapshots
+
+run 'export DIGITALOCEAN_TOKEN=$my_api_token'";
+
+const TOKEN_ENV_VARS: [&str; 2] = ["DIGITALOCEAN_TOKEN", "DIGITALOCEAN_ACCESS_TOKEN"];
+
+#[derive(Clone)]
+pub struct DigitalOceanConfig {
+    pub region: String,
+    pub size: String,
+}
+
+impl DigitalOceanConfig {
+    const DEFAULT_REGION: &'static str = "nyc3";
+    const DEFAULT_SIZE: &'static str = "s-1vcpu-1gb";
+
+    pub fn default() -> Self {
+        Self {
+            region: Self::DEFAULT_REGION.to_string(),
+            size: Self::DEFAULT_SIZE.to_string(),
+        }
+    }
+
+    pub fn parse(yaml: &YamlNode, errors: &mut ConfigErrors) -> Self {
+        yaml.mapping(&["region", "size"], errors);
+        Self {
+            region: yaml.get("region").string(errors).unwrap_or_else(|| Self::DEFAULT_REGION.to_string()),
+            size: yaml.get("size").string(errors).unwrap_or_else(|| Self::DEFAULT_SIZE.to_string()),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct DigitalOceanContext {
+    pub region: String,
+    pub size: String,
+}
+
+impl DigitalOceanContext {
+    pub async fn init(path: PathBuf, cfg: &SwoonConfig) -> task::Result<Self> {
+        let doctl_cli = DoctlCli::new(path);
+        let (
+            auth_context_result,
+            account_status_result,
+        ) = join!(
+            doctl_cli.current_auth_context(),
+            doctl_cli.account_status(),
+        );
+
+        let auth_context = auth_context_result?;
+        let account_status = account_status_result?;
+        if account_status != "active" {
+            return task::Error::result(format!(
+                "DigitalOcean account for doctl auth context {} is {}", auth_context, account_status));
+        }
+
+        if !TOKEN_ENV_VARS.iter().any(|v| env::var(v).is_ok_and(|t| !t.is_empty())) {
+            return task::Error::result(ERR_API_TOKEN);
+        }
+
+        Ok(Self {
+            region: cfg.environment.as_ref().and_then(|e| e.region.clone())
+                .unwrap_or_else(|| cfg.platforms.digitalocean.region.clone()),
+            size: cfg.platforms.digitalocean.size.clone(),
+        })
+    }
+}
@@ -0,0 +1,78 @@
+use crate::api::config::SwoonConfig;
+use crate::api::task;
+use crate::api::output::template::{Template, template_object};
+use crate::images::{ImageSource, ImageSpec};
+use crate::images::provenance::Provenance;
+use crate::packer::{PackerPlugin, PackerSource, source};
+use crate::platforms::digitalocean::{DigitalOceanContext, images};
+
+pub struct DigitalOceanPackerSource {
+    image_name: String,
+    image_slug: String,
+    region: String,
+    size: String,
+    source_label: String,
+    tags: Vec<String>,
+}
+
+impl DigitalOceanPackerSource {
+    pub fn from_spec(cfg: &SwoonConfig,
+                     digitalocean_ctx: &DigitalOceanContext,
+                     spec: &ImageSpec) -> task::Result<Box<Self>> {
+        let os = match &spec.source {
+            ImageSource::OperatingSystem { os } => os,
+            ImageSource::Image { .. } | ImageSource::Family { .. } => return source::unsupported_image_source(spec),
+        };
+        Ok(Box::from(Self {
+            image_name: spec.image_name(cfg),
+            image_slug: images::image_slug_by_os(os)?,
+            region: digitalocean_ctx.region.clone(),
+            size: digitalocean_ctx.size.clone(),
+            source_label: spec.source_label(),
+            tags: Vec::new(),
+        }))
+    }
+}
+
+impl PackerSource for DigitalOceanPackerSource {
+    fn name(&self) -> String {
+        format!("source.digitalocean.{}", self.source_label)
+    }
+
+    fn plugin(&self) -> PackerPlugin {
+        PackerPlugin {
+            name: "digitalocean".into(),
+            source: "github.com/digitalocean/digitalocean".into(),
+            version: ">= 1.0.0".into(),
+        }
+    }
+
+    fn source_image(&self) -> String {
+        self.image_slug.clone()
+    }
+
+    fn set_provenance(&mut self, provenance: &Provenance) -> task::Result<()> {
+        self.tags = provenance.labels().iter()
+            .map(|(name, value)| images::tag(name, value))
+            .collect();
+        task::SUCCESS
+    }
+
+    fn to_hcl(&self) -> String {
+        let result = Template::render(
+            include_bytes!("source.pkr.hcl.liquid"),
+            &template_object!({
+                "image_name": self.image_name,
+                "image_slug": self.image_slug,
+                "region": self.region,
+                "size": self.size,
+                "source_label": self.source_label,
+                "tags": self.tags,
+            }),
+        );
+        match result {
+            Ok(s) => s,
+            Err(e) => e.exit(),
+        }
+    }
+}
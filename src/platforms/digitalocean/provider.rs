@@ -0,0 +1,35 @@
+use futures::executor;
+
+use crate::api::{CloudPlatform, task};
+use crate::api::binaries::{BinaryPaths, PathLookup};
+use crate::api::config::SwoonConfig;
+use crate::images::ImageSpec;
+use crate::packer::PackerSource;
+use crate::platforms::digitalocean::DigitalOceanContext;
+use crate::platforms::digitalocean::packer::DigitalOceanPackerSource;
+use crate::platforms::images::regional_image_id;
+use crate::platforms::provider::PlatformProvider;
+
+pub struct DigitalOceanProvider {
+    digitalocean_ctx: DigitalOceanContext,
+}
+
+impl PlatformProvider for DigitalOceanProvider {
+    fn init(binary_paths: &BinaryPaths, cfg: &SwoonConfig, _: &CloudPlatform) -> task::Result<Self> {
+        Ok(Self {
+            digitalocean_ctx: executor::block_on(DigitalOceanContext::init(binary_paths.doctl_path()?, cfg))?,
+        })
+    }
+
+    fn platform(&self) -> CloudPlatform {
+        CloudPlatform::DigitalOcean
+    }
+
+    fn image_id_from_artifact(&self, artifact_id: &str) -> String {
+        regional_image_id(artifact_id)
+    }
+
+    fn packer_source(&self, cfg: &SwoonConfig, spec: &ImageSpec) -> task::Result<Box<dyn PackerSource>> {
+        Ok(DigitalOceanPackerSource::from_spec(cfg, &self.digitalocean_ctx, spec)?)
+    }
+}
@@ -0,0 +1,194 @@
+use std::path::PathBuf;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use which::which;
+
+use crate::api::{CloudPlatform, task};
+use crate::api::process::Process;
+
+pub mod packer;
+pub mod provider;
+
+/// Executables named with this prefix on PATH provide the platform named by the rest of the filename.
+pub const PLUGIN_PREFIX: &str = "swoon-platform-";
+
+/// Version of the json protocol sent to plugins with every request.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Resolves the `swoon-platform-<name>` executable on PATH providing a platform.
+pub fn plugin_path(name: &str) -> Option<PathBuf> {
+    if !is_plugin_name(name) {
+        return None;
+    }
+    which(format!("{}{}", PLUGIN_PREFIX, name)).ok()
+}
+
+/// Parses a platform named in swoon.yml, requiring the plugin of an external platform to be on
+/// PATH so that a misspelt platform is reported where it is written rather than when baking.
+pub fn parse_configured_platform(label: &str) -> task::Result<CloudPlatform> {
+    match CloudPlatform::parse(label)? {
+        CloudPlatform::External(name) if plugin_path(&name).is_none() => missing_plugin_result(&name),
+        platform => Ok(platform),
+    }
+}
+
+fn missing_plugin_result<T>(name: &str) -> task::Result<T> {
+    task::Error::result(format!(
+        "cloud platform {} is not one of aws, azure, digitalocean, gcp, linode or vultr \
+        and there is no {}{} executable on PATH", name, PLUGIN_PREFIX, name))
+}
+
+/// Plugin names are lowercase alphanumeric words separated by hyphens, so that a name is safe
+/// to use in executable filenames, swoon.yml keys and image names.
+pub fn is_plugin_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('-')
+        && !name.ends_with('-')
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+/// A platform plugin executable, invoked once per request with the method as its only arg.
+///
+/// The request is written to the plugin's stdin as a json object with the protocol version,
+/// the context the plugin returned from its init method and the params of the method:
+///
+/// ```json
+/// {"protocol": 1, "context": {"project": "acme"}, "params": {"prefix": "acme-archetype-"}}
+/// ```
+///
+/// The plugin writes a json object to stdout with either the result of the method or an error
+/// message, and can write progress for the user to stderr. Methods and their results are:
+///
+/// - `init` with the org_name, environment and the plugin's swoon.yml `platforms` config,
+///   returning a context that is sent with every later request, which can list the optional
+///   features of the plugin as `"capabilities": ["image-families", "deprecate-image"]`
+/// - `current-os-image` with an os like `debian:11`, returning the current image or null
+/// - `render-source` with an image spec and labels, returning the packer source's name, plugin,
+///   source_image and hcl
+/// - `images-by-prefix` with a prefix, returning images with name, id, created_at, status and
+///   the optional inputs_hash from the image's `swoon-inputs-hash` label
+/// - `delete-image` with an image's name and id, returning null
+/// - `deprecate-image` with an image's name and id, a deprecation state and the name of the
+///   replacement image, returning null, for plugins with the `deprecate-image` capability
+#[derive(Clone)]
+pub struct ExternalPlugin {
+    pub name: String,
+    path: PathBuf,
+    context: Value,
+}
+
+#[derive(Serialize)]
+struct PluginRequest<'a> {
+    protocol: u32,
+    context: &'a Value,
+    params: Value,
+}
+
+#[derive(Deserialize)]
+struct PluginResponse {
+    #[serde(default)]
+    result: Value,
+    error: Option<String>,
+}
+
+impl ExternalPlugin {
+    pub fn new(name: &str) -> task::Result<Self> {
+        match plugin_path(name) {
+            None => missing_plugin_result(name),
+            Some(path) => Ok(Self {
+                name: name.to_string(),
+                path,
+                context: Value::Null,
+            }),
+        }
+    }
+
+    /// Initializes the plugin's context, resolving the account, project or region of the platform.
+    pub fn init(mut self, org_name: &str, environment: Option<&str>, config: Value) -> task::Result<Self> {
+        self.context = self.request("init", json!({
+            "org_name": org_name,
+            "environment": environment,
+            "config": config,
+        }))?;
+        Ok(self)
+    }
+
+    /// Whether the plugin's init context lists an optional feature in its capabilities.
+    pub fn has_capability(&self, capability: &str) -> bool {
+        self.context["capabilities"].as_array()
+            .is_some_and(|capabilities| capabilities.iter().any(|c| c.as_str() == Some(capability)))
+    }
+
+    pub fn request<T: DeserializeOwned>(&self, method: &str, params: Value) -> task::Result<T> {
+        let request = PluginRequest {
+            protocol: PROTOCOL_VERSION,
+            context: &self.context,
+            params,
+        };
+        let output = Process::exchange(&self.path, [method], &serde_json::to_string(&request)?)?;
+        self.parse_response(method, &output)
+    }
+
+    fn parse_response<T: DeserializeOwned>(&self, method: &str, output: &str) -> task::Result<T> {
+        let response: PluginResponse = match serde_json::from_str(output) {
+            Ok(response) => response,
+            Err(e) => return task::Error::result(format!(
+                "{}{} {} did not respond with json: {}", PLUGIN_PREFIX, self.name, method, e)),
+        };
+        if let Some(error) = response.error {
+            return task::Error::result(format!("{}{} {}: {}", PLUGIN_PREFIX, self.name, method, error));
+        }
+        match serde_json::from_value(response.result) {
+            Ok(result) => Ok(result),
+            Err(e) => task::Error::result(format!(
+                "{}{} {} responded with an unexpected result: {}", PLUGIN_PREFIX, self.name, method, e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plugin() -> ExternalPlugin {
+        ExternalPlugin {
+            name: "hetzner".to_string(),
+            path: PathBuf::from("swoon-platform-hetzner"),
+            context: Value::Null,
+        }
+    }
+
+    #[test]
+    fn test_is_plugin_name() {
+        assert!(is_plugin_name("hetzner"));
+        assert!(is_plugin_name("open-stack2"));
+        assert!(!is_plugin_name(""));
+        assert!(!is_plugin_name("-hetzner"));
+        assert!(!is_plugin_name("Hetzner"));
+        assert!(!is_plugin_name("../hetzner"));
+    }
+
+    #[test]
+    fn test_has_capability() {
+        let plugin = ExternalPlugin {
+            context: json!({"project": "acme", "capabilities": ["image-families"]}),
+            ..plugin()
+        };
+        assert!(plugin.has_capability("image-families"));
+        assert!(!plugin.has_capability("deprecate-image"));
+        assert!(!self::plugin().has_capability("image-families"));
+    }
+
+    #[test]
+    fn test_parse_response() {
+        let image: Option<String> = plugin().parse_response("current-os-image", r#"{"result": "debian-11-v1"}"#).unwrap();
+        assert_eq!(image, Some("debian-11-v1".to_string()));
+        let image: Option<String> = plugin().parse_response("current-os-image", r#"{"result": null}"#).unwrap();
+        assert_eq!(image, None);
+        let error = plugin().parse_response::<Value>("init", r#"{"error": "not logged in"}"#).unwrap_err();
+        assert_eq!(error.to_string(), "swoon-platform-hetzner init: not logged in");
+        assert!(plugin().parse_response::<Value>("init", "not logged in").is_err());
+    }
+}
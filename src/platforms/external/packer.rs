@@ -0,0 +1,148 @@
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+use crate::api::config::SwoonConfig;
+use crate::api::task;
+use crate::images::{ImageSource, ImageSpec};
+use crate::images::provenance::Provenance;
+use crate::packer::{PackerPlugin, PackerSource};
+use crate::platforms::external::ExternalPlugin;
+
+#[derive(Deserialize)]
+struct RenderedPlugin {
+    name: String,
+    source: String,
+    version: String,
+}
+
+/// A packer source rendered by a plugin's `render-source` method.
+#[derive(Deserialize)]
+struct RenderedSource {
+    name: String,
+    plugin: RenderedPlugin,
+    source_image: String,
+    hcl: String,
+}
+
+/// Packer source of an external platform, rendered by the plugin again when labels are added
+/// so that the plugin can apply them with the platform's own rules for labels or tags.
+pub struct ExternalPackerSource {
+    plugin: ExternalPlugin,
+    spec: Value,
+    labels: Map<String, Value>,
+    rendered: RenderedSource,
+}
+
+impl ExternalPackerSource {
+    pub fn from_spec(cfg: &SwoonConfig, plugin: ExternalPlugin, spec: &ImageSpec) -> task::Result<Box<Self>> {
+        let mut labels = Map::new();
+        labels.insert("swoon-org".to_string(), Value::from(cfg.org_name.clone()));
+        labels.insert("swoon-image".to_string(), Value::from(spec.label.clone()));
+        let spec = Self::spec_params(cfg, spec);
+        let rendered = Self::render(&plugin, &spec, &labels)?;
+        Ok(Box::from(Self {
+            plugin,
+            spec,
+            labels,
+            rendered,
+        }))
+    }
+
+    fn spec_params(cfg: &SwoonConfig, spec: &ImageSpec) -> Value {
+        let os = spec.os().to_string();
+        let source = match &spec.source {
+            ImageSource::OperatingSystem { .. } => json!({"os": os}),
+            ImageSource::Image { name, .. } => json!({"os": os, "image": name}),
+            ImageSource::Family { name, .. } => json!({"os": os, "family": name}),
+        };
+        json!({
+            "label": spec.label,
+            "image_name": spec.image_name(cfg),
+            "image_family": spec.image_family(cfg),
+            "image_description": spec.image_description(cfg),
+            "source_label": spec.source_label(),
+            "source": source,
+        })
+    }
+
+    fn render(plugin: &ExternalPlugin, spec: &Value, labels: &Map<String, Value>) -> task::Result<RenderedSource> {
+        plugin.request("render-source", json!({
+            "spec": spec,
+            "labels": labels,
+        }))
+    }
+}
+
+impl PackerSource for ExternalPackerSource {
+    fn name(&self) -> String {
+        self.rendered.name.clone()
+    }
+
+    fn plugin(&self) -> PackerPlugin {
+        PackerPlugin {
+            name: self.rendered.plugin.name.clone().into(),
+            source: self.rendered.plugin.source.clone().into(),
+            version: self.rendered.plugin.version.clone().into(),
+        }
+    }
+
+    fn source_image(&self) -> String {
+        self.rendered.source_image.clone()
+    }
+
+    fn set_provenance(&mut self, provenance: &Provenance) -> task::Result<()> {
+        for (name, value) in provenance.labels() {
+            self.labels.insert(name.to_string(), Value::from(value));
+        }
+        self.rendered = Self::render(&self.plugin, &self.spec, &self.labels)?;
+        task::SUCCESS
+    }
+
+    fn to_hcl(&self) -> String {
+        self.rendered.hcl.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::{CloudPlatform, DEBIAN_11};
+    use crate::api::config::{ArchetypeConfig, BinariesConfig, ImageNamingConfig, ImageRetentionConfig};
+    use crate::platforms::PlatformConfigs;
+
+    use super::*;
+
+    #[test]
+    fn test_spec_params() {
+        let cfg = SwoonConfig {
+            org_name: "eighty4".to_string(),
+            default_os: DEBIAN_11,
+            default_platform: CloudPlatform::External("hetzner".to_string()),
+            archetype: ArchetypeConfig {
+                platforms: vec!(CloudPlatform::External("hetzner".to_string())),
+                operating_systems: vec!(DEBIAN_11),
+            },
+            environments: Vec::new(),
+            environment: None,
+            image_naming: ImageNamingConfig::default(),
+            image_retention: ImageRetentionConfig::default(),
+            pin_source_images: false,
+            instances: Vec::new(),
+            platforms: PlatformConfigs::default(),
+            binaries: BinariesConfig::default(),
+        };
+        let spec = ImageSpec {
+            label: "postgres".to_string(),
+            platform: CloudPlatform::External("hetzner".to_string()),
+            source: ImageSource::Family { name: "eighty4-archetype-debian-11".to_string(), os: DEBIAN_11 },
+            version: "1".to_string(),
+        };
+        assert_eq!(ExternalPackerSource::spec_params(&cfg, &spec), json!({
+            "label": "postgres",
+            "image_name": "eighty4-postgres-debian-11-1",
+            "image_family": "eighty4-postgres-debian-11",
+            "image_description": "eighty4 postgres image for debian:11 baked by swoon",
+            "source_label": "postgres-debian-11",
+            "source": {"os": "debian:11", "family": "eighty4-archetype-debian-11"},
+        }));
+    }
+}
@@ -0,0 +1,104 @@
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::api::{CloudPlatform, OperatingSystem, task};
+use crate::api::binaries::BinaryPaths;
+use crate::api::config::{DeprecationState, SwoonConfig};
+use crate::api::util::parse_timestamp;
+use crate::images::ImageSpec;
+use crate::packer::PackerSource;
+use crate::platforms::external::ExternalPlugin;
+use crate::platforms::external::packer::ExternalPackerSource;
+use crate::platforms::images::{latest_in_family, PlatformImage};
+use crate::platforms::provider::PlatformProvider;
+
+/// An image listed by a plugin, with an RFC 3339 created_at timestamp.
+#[derive(Deserialize)]
+struct ExternalImage {
+    name: String,
+    id: String,
+    created_at: String,
+    status: String,
+    #[serde(default)]
+    inputs_hash: Option<String>,
+}
+
+impl ExternalImage {
+    fn to_platform_image(&self) -> task::Result<PlatformImage> {
+        Ok(PlatformImage {
+            name: self.name.clone(),
+            id: self.id.clone(),
+            created_at: parse_timestamp(&self.created_at)?,
+            status: self.status.to_lowercase(),
+            inputs_hash: self.inputs_hash.clone(),
+        })
+    }
+}
+
+pub struct ExternalProvider {
+    plugin: ExternalPlugin,
+}
+
+impl ExternalProvider {
+    fn plugin_images_by_prefix(&self, prefix: &str) -> task::Result<Vec<PlatformImage>> {
+        let images: Vec<ExternalImage> = self.plugin.request("images-by-prefix", json!({"prefix": prefix}))?;
+        images.iter().map(ExternalImage::to_platform_image).collect()
+    }
+}
+
+impl PlatformProvider for ExternalProvider {
+    fn init(_: &BinaryPaths, cfg: &SwoonConfig, platform: &CloudPlatform) -> task::Result<Self> {
+        let name = platform.to_str();
+        let environment = cfg.environment.as_ref().map(|environment| environment.name.as_str());
+        Ok(Self {
+            plugin: ExternalPlugin::new(name)?.init(&cfg.org_name, environment, cfg.platforms.external_config(name))?,
+        })
+    }
+
+    fn supports_instance_images() -> bool {
+        true
+    }
+
+    fn platform(&self) -> CloudPlatform {
+        CloudPlatform::External(self.plugin.name.clone())
+    }
+
+    fn has_image_families(&self) -> bool {
+        self.plugin.has_capability("image-families")
+    }
+
+    fn supports_deprecation(&self) -> bool {
+        self.plugin.has_capability("deprecate-image")
+    }
+
+    fn current_os_image(&self, os: &OperatingSystem) -> task::Result<Option<String>> {
+        self.plugin.request("current-os-image", json!({"os": os.to_string()}))
+    }
+
+    fn packer_source(&self, cfg: &SwoonConfig, spec: &ImageSpec) -> task::Result<Box<dyn PackerSource>> {
+        Ok(ExternalPackerSource::from_spec(cfg, self.plugin.clone(), spec)?)
+    }
+
+    fn images_by_prefix(&self, prefix: &str) -> task::Result<Option<Vec<PlatformImage>>> {
+        Ok(Some(self.plugin_images_by_prefix(prefix)?))
+    }
+
+    fn latest_image(&self, prefix: &str) -> task::Result<Option<String>> {
+        Ok(latest_in_family(prefix, self.plugin_images_by_prefix(prefix)?).map(|image| image.name))
+    }
+
+    fn delete_image(&self, image: &PlatformImage) -> task::Result<()> {
+        self.plugin.request::<Value>("delete-image", json!({"name": image.name, "id": image.id}))?;
+        task::SUCCESS
+    }
+
+    fn deprecate_image(&self, image: &PlatformImage, state: &DeprecationState, replacement: &str) -> task::Result<()> {
+        self.plugin.request::<Value>("deprecate-image", json!({
+            "name": image.name,
+            "id": image.id,
+            "state": state.to_str(),
+            "replacement": replacement,
+        }))?;
+        task::SUCCESS
+    }
+}
@@ -4,6 +4,7 @@ use crate::api::{OperatingSystem, task};
 use crate::api::process::Process;
 use crate::api::util::split_string;
 use crate::platforms::gcloud::images;
+use crate::platforms::images::PlatformImage;
 
 // todo[gcloud] check if cli is up to date
 //  gcloud version
@@ -13,6 +14,10 @@ const ERR_DEFAULT_PROJECT: &str = r"gcloud does not have a configured default pr
 
 run 'gcloud configure set core/project $my_project_id'";
 
+const ERR_DEFAULT_ZONE: &str = r"gcloud does not have a configured default zone
+
+run 'gcloud config set compute/zone $my_zone' or set zone in the platforms gcp section of swoon.yml";
+
 pub struct GcloudCli {
     path: PathBuf,
 }
@@ -48,34 +53,152 @@ impl GcloudCli {
         }
     }
 
+    pub async fn default_zone(&self) -> task::Result<String> {
+        let default_zone = Process::invoke(&self.path, [
+            "config",
+            "get-value",
+            "compute/zone",
+            "-q",
+        ])?;
+        if default_zone.is_empty() || default_zone.eq("(unset)") {
+            task::Error::result(ERR_DEFAULT_ZONE)
+        } else {
+            Ok(default_zone)
+        }
+    }
+
+    /// Resolves the current image in the OS image family.
     pub async fn image_name_by_os(&self, os: &OperatingSystem) -> task::Result<String> {
-        Process::invoke(&self.path, [
+        let family = images::family_name_by_os(os);
+        let image_name = Process::invoke(&self.path, [
             "compute",
             "images",
             "list",
             "--format=value(name)",
-            format!("--filter=family:{}", images::family_name_by_os(os)).as_ref(),
+            format!("--filter=family={}", family).as_ref(),
+            "--sort-by=~creationTimestamp",
+            "--limit=1",
             "-q",
-        ])
+        ])?;
+        if image_name.is_empty() {
+            task::Error::result(format!("no image found in image family {}", family))
+        } else {
+            Ok(image_name)
+        }
     }
-}
 
-// pub async fn all_image_names(ctx: &SwoonContext) -> task::Result<Vec<String>> {
-//     let org_name = match &ctx.config {
-//         Some(cfg) => &cfg.org_name,
-//         None => return task::Error::result("no config"),
-//     };
-//     let output = Process::invoke(&ctx.gcloud_path(), [
-//         "compute",
-//         "images",
-//         "list",
-//         "--no-standard-images",
-//         "--format=value(NAME)",
-//         format!("--filter=name:{}-{}-", org_name, "archetype").as_ref(),
-//         "-q",
-//     ])?;
-//     Ok(split_string("\n", output))
-// }
+    /// Resolves the image an image family of the project points to, skipping deprecated images.
+    pub async fn image_name_by_family(&self, project_id: &str, family: &str) -> task::Result<String> {
+        let image_name = Process::invoke(&self.path, [
+            "compute",
+            "images",
+            "list",
+            format!("--project={}", project_id).as_ref(),
+            "--no-standard-images",
+            "--format=value(name)",
+            format!("--filter=family={} AND -deprecated.state:*", family).as_ref(),
+            "--sort-by=~creationTimestamp",
+            "--limit=1",
+            "-q",
+        ])?;
+        if image_name.is_empty() {
+            task::Error::result(format!("no image found in image family {} of project {}", family, project_id))
+        } else {
+            Ok(image_name)
+        }
+    }
+
+    /// Lists images with their deprecation state reported as the status of deprecated images.
+    pub async fn images_by_prefix(&self, project_id: &str, prefix: &str) -> task::Result<Vec<PlatformImage>> {
+        let output = Process::invoke(&self.path, [
+            "compute",
+            "images",
+            "list",
+            format!("--project={}", project_id).as_ref(),
+            "--no-standard-images",
+            "--format=value(name,id,creationTimestamp,status,deprecated.state,labels.swoon-inputs-hash)",
+            format!("--filter=name~^{}", prefix).as_ref(),
+            "-q",
+        ])?;
+        PlatformImage::parse_lines(&output)
+    }
+
+    pub async fn delete_image(&self, project_id: &str, image_name: &str) -> task::Result<()> {
+        Process::invoke(&self.path, [
+            "compute",
+            "images",
+            "delete",
+            image_name,
+            format!("--project={}", project_id).as_ref(),
+            "-q",
+        ])?;
+        task::SUCCESS
+    }
+
+    /// Marks an image with a deprecation state, pointing users of the image to its replacement.
+    pub async fn deprecate_image(&self,
+                                 project_id: &str,
+                                 image_name: &str,
+                                 state: &str,
+                                 replacement: &str) -> task::Result<()> {
+        Process::invoke(&self.path, [
+            "compute",
+            "images",
+            "deprecate",
+            image_name,
+            format!("--project={}", project_id).as_ref(),
+            format!("--state={}", state).as_ref(),
+            format!("--replacement={}", replacement).as_ref(),
+            "-q",
+        ])?;
+        task::SUCCESS
+    }
+
+    /// Creates an image in a project from an image in another project.
+    /// Labels of an image, read to copy them onto promoted images.
+    pub async fn image_labels(&self, project_id: &str, image_name: &str) -> task::Result<Vec<(String, String)>> {
+        let output = Process::invoke(&self.path, [
+            "compute",
+            "images",
+            "describe",
+            image_name,
+            format!("--project={}", project_id).as_ref(),
+            "--format=json(labels)",
+            "-q",
+        ])?;
+        let described: serde_json::Value = serde_json::from_str(&output)?;
+        Ok(match described["labels"].as_object() {
+            None => Vec::new(),
+            Some(labels) => labels.iter()
+                .map(|(name, value)| (name.clone(), value.as_str().unwrap_or_default().to_string()))
+                .collect(),
+        })
+    }
+
+    pub async fn copy_image(&self,
+                            source_project_id: &str,
+                            source_image_name: &str,
+                            project_id: &str,
+                            image_name: &str,
+                            labels: &[(String, String)]) -> task::Result<()> {
+        let labels = labels.iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<String>>()
+            .join(",");
+        Process::invoke(&self.path, [
+            "compute",
+            "images",
+            "create",
+            image_name,
+            format!("--project={}", project_id).as_ref(),
+            format!("--source-image={}", source_image_name).as_ref(),
+            format!("--source-image-project={}", source_project_id).as_ref(),
+            format!("--labels={}", labels).as_ref(),
+            "-q",
+        ])?;
+        task::SUCCESS
+    }
+}
 
 // #[cfg(test)]
 // mod tests {
@@ -1,4 +1,4 @@
-use crate::api::OperatingSystem;
+use crate::api::{OperatingSystem, task};
 
 pub fn family_name_by_os(os: &OperatingSystem) -> String {
     match os {
@@ -9,3 +9,48 @@ pub fn family_name_by_os(os: &OperatingSystem) -> String {
         }
     }
 }
+
+/// Image names must match `[a-z]([-a-z0-9]*[a-z0-9])?` and be at most 63 characters.
+pub fn validate_image_name(name: &str) -> task::Result<()> {
+    let valid = name.len() <= 63
+        && name.starts_with(|c: char| c.is_ascii_lowercase())
+        && !name.ends_with('-')
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+    if valid {
+        task::SUCCESS
+    } else {
+        task::Error::result(format!(
+            "{} is not a valid GCP image name of at most 63 lowercase letters, digits and hyphens", name))
+    }
+}
+
+/// Label values are at most 63 lowercase letters, digits, underscores and hyphens.
+pub fn sanitize_label_value(value: &str) -> String {
+    value.to_lowercase().chars()
+        .map(|c| if c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' { c } else { '-' })
+        .take(63)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_label_value() {
+        assert_eq!(sanitize_label_value("debian:11"), "debian-11");
+        assert_eq!(sanitize_label_value("0.0.1"), "0-0-1");
+        assert_eq!(sanitize_label_value(&"a".repeat(64)).len(), 63);
+    }
+
+    #[test]
+    fn test_validate_image_name() {
+        assert!(validate_image_name("eighty4-archetype-debian-11-20220104153012").is_ok());
+        assert!(validate_image_name("eighty4-archetype-debian-11-20220104153012-a1b2c3d").is_ok());
+        assert!(validate_image_name("Eighty4-archetype-debian-11-1").is_err());
+        assert!(validate_image_name("4-archetype-debian-11-1").is_err());
+        assert!(validate_image_name("eighty4-archetype-debian-11-").is_err());
+        assert!(validate_image_name("eighty4_archetype").is_err());
+        assert!(validate_image_name(&"a".repeat(64)).is_err());
+    }
+}
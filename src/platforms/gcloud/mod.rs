@@ -4,15 +4,87 @@ use futures::join;
 
 use crate::api::task;
 use crate::api::config::SwoonConfig;
+use crate::api::yaml::{ConfigErrors, YamlNode};
 use crate::platforms::gcloud::cli::GcloudCli;
 
 pub mod cli;
 pub mod images;
 pub mod packer;
+pub mod provider;
+
+/// Settings for the VMs packer launches to bake images on GCP.
+#[derive(Clone)]
+pub struct GcloudConfig {
+    /// Defaults to the compute/zone of the gcloud config.
+    pub zone: Option<String>,
+    pub machine_type: String,
+    /// Boot disk size in GB.
+    pub disk_size: Option<u32>,
+    pub disk_type: Option<String>,
+    pub subnetwork: Option<String>,
+    pub tags: Vec<String>,
+    pub preemptible: bool,
+    /// Spot VMs replace preemptible VMs without their 24 hour limit, so only one can be set.
+    pub spot: bool,
+    pub use_iap: bool,
+}
+
+impl GcloudConfig {
+    const DEFAULT_MACHINE_TYPE: &'static str = "e2-medium";
+
+    pub fn default() -> Self {
+        Self {
+            zone: None,
+            machine_type: Self::DEFAULT_MACHINE_TYPE.to_string(),
+            disk_size: None,
+            disk_type: None,
+            subnetwork: None,
+            tags: Vec::new(),
+            preemptible: false,
+            spot: false,
+            use_iap: false,
+        }
+    }
+
+    pub fn parse(yaml: &YamlNode, errors: &mut ConfigErrors) -> Self {
+        yaml.mapping(&[
+            "zone",
+            "machine_type",
+            "disk_size",
+            "disk_type",
+            "subnetwork",
+            "tags",
+            "preemptible",
+            "spot",
+            "use_iap",
+        ], errors);
+        let tags = yaml.get("tags").sequence(errors).iter()
+            .filter_map(|tag| tag.string(errors))
+            .collect();
+        let preemptible = yaml.get("preemptible").bool(errors).unwrap_or(false);
+        let spot = yaml.get("spot").bool(errors).unwrap_or(false);
+        if preemptible && spot {
+            errors.add(yaml, "preemptible and spot can not both be set");
+        }
+        Self {
+            zone: yaml.get("zone").string(errors),
+            machine_type: yaml.get("machine_type").string(errors)
+                .unwrap_or_else(|| Self::DEFAULT_MACHINE_TYPE.to_string()),
+            disk_size: yaml.get("disk_size").positive_u32("GB", errors),
+            disk_type: yaml.get("disk_type").string(errors),
+            subnetwork: yaml.get("subnetwork").string(errors),
+            tags,
+            preemptible,
+            spot,
+            use_iap: yaml.get("use_iap").bool(errors).unwrap_or(false),
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct GcloudContext {
     pub default_project_id: String,
+    pub zone: String,
 }
 
 impl GcloudContext {
@@ -27,15 +99,25 @@ impl GcloudContext {
         );
 
         let all_projects: Vec<String> = all_projects_result?;
-        let default_project_id = default_project_id_result?;
+        let environment = cfg.environment.as_ref();
+        let default_project_id = match environment.and_then(|e| e.project.clone()) {
+            Some(project_id) => project_id,
+            None => default_project_id_result?,
+        };
 
         if !all_projects.contains(&default_project_id) {
             return task::Error::result(format!(
                 "{} is not a project for your authed gcloud session", default_project_id));
         }
 
+        let zone = match environment.and_then(|e| e.zone.as_ref()).or(cfg.platforms.gcp.zone.as_ref()) {
+            Some(zone) => zone.clone(),
+            None => gcloud_cli.default_zone().await?,
+        };
+
         Ok(Self {
             default_project_id,
+            zone,
         })
     }
 }
@@ -1,8 +1,13 @@
+use futures::executor;
+
 use crate::api::config::SwoonConfig;
-use crate::api::OperatingSystem;
+use crate::api::task;
 use crate::api::output::template::{Template, template_object};
-use crate::packer::PackerSource;
-use crate::platforms::gcloud::{GcloudContext, images};
+use crate::images::{ImageSource, ImageSpec};
+use crate::images::provenance::Provenance;
+use crate::packer::{PackerPlugin, PackerSource};
+use crate::platforms::gcloud::{GcloudConfig, GcloudContext, images};
+use crate::platforms::gcloud::cli::GcloudCli;
 
 enum SourceImageMethod {
     Family(String),
@@ -10,32 +15,58 @@ enum SourceImageMethod {
 }
 
 impl SourceImageMethod {
-    fn from_os(os: &OperatingSystem) -> Self {
-        SourceImageMethod::Family(images::family_name_by_os(os))
+    fn from_spec(spec: &ImageSpec) -> Self {
+        match &spec.source {
+            ImageSource::OperatingSystem { os } => SourceImageMethod::Family(images::family_name_by_os(os)),
+            ImageSource::Image { name, .. } => SourceImageMethod::Name(name.clone()),
+            ImageSource::Family { name, .. } => SourceImageMethod::Family(name.clone()),
+        }
     }
 }
 
 pub struct GcloudPackerSource {
+    config: GcloudConfig,
+    image_description: String,
+    image_family: String,
+    image_labels: Vec<(String, String)>,
     image_name: String,
     project_id: String,
+    source_image: String,
     source_label: String,
     source_method: SourceImageMethod,
+    zone: String,
 }
 
 impl GcloudPackerSource {
-    pub fn from_os(cfg: &SwoonConfig, gcloud_ctx: &GcloudContext, os: &OperatingSystem) -> Box<Self> {
-        Self::new(cfg, gcloud_ctx, SourceImageMethod::from_os(os))
+    pub fn from_spec(cfg: &SwoonConfig,
+                     gcloud_ctx: &GcloudContext,
+                     gcloud_cli: GcloudCli,
+                     spec: &ImageSpec) -> task::Result<Box<Self>> {
+        let source_image = match &spec.source {
+            ImageSource::OperatingSystem { os } => executor::block_on(gcloud_cli.image_name_by_os(os))?,
+            ImageSource::Image { name, .. } => name.clone(),
+            ImageSource::Family { name, .. } => executor::block_on(
+                gcloud_cli.image_name_by_family(&gcloud_ctx.default_project_id, name))?,
+        };
+        Ok(Box::from(Self {
+            config: cfg.platforms.gcp.clone(),
+            image_description: spec.image_description(cfg),
+            image_family: spec.image_family(cfg),
+            image_labels: Self::image_labels(&cfg.org_name, &spec.label),
+            image_name: spec.image_name(cfg),
+            project_id: gcloud_ctx.default_project_id.clone(),
+            source_image,
+            source_label: spec.source_label(),
+            source_method: SourceImageMethod::from_spec(spec),
+            zone: gcloud_ctx.zone.clone(),
+        }))
     }
 
-    fn new(cfg: &SwoonConfig,
-           gcloud_ctx: &GcloudContext,
-           source_method: SourceImageMethod) -> Box<Self> {
-        Box::from(Self {
-            image_name: format!("{}-archetype-{}", cfg.org_name, "2020-01-02").to_string(),
-            project_id: gcloud_ctx.default_project_id.clone(),
-            source_label: "archetype".to_string(),
-            source_method,
-        })
+    fn image_labels(org_name: &str, label: &str) -> Vec<(String, String)> {
+        vec!(
+            ("swoon-org".to_string(), images::sanitize_label_value(org_name)),
+            ("swoon-image".to_string(), images::sanitize_label_value(label)),
+        )
     }
 }
 
@@ -44,6 +75,23 @@ impl PackerSource for GcloudPackerSource {
         format!("source.googlecompute.{}", self.source_label)
     }
 
+    fn plugin(&self) -> PackerPlugin {
+        PackerPlugin {
+            name: "googlecompute".into(),
+            source: "github.com/hashicorp/googlecompute".into(),
+            version: ">= 1.0.0".into(),
+        }
+    }
+
+    fn source_image(&self) -> String {
+        self.source_image.clone()
+    }
+
+    fn set_provenance(&mut self, provenance: &Provenance) -> task::Result<()> {
+        self.image_labels.extend(provenance.sanitized_short_labels(images::sanitize_label_value));
+        task::SUCCESS
+    }
+
     fn to_hcl(&self) -> String {
         let (source_image_method, source_image_value) = match &self.source_method {
             SourceImageMethod::Family(v) => ("source_image_family", v),
@@ -55,8 +103,20 @@ impl PackerSource for GcloudPackerSource {
                 "project_id": self.project_id,
                 "source_image_method": source_image_method,
                 "source_image_value": source_image_value,
+                "image_description": self.image_description,
+                "image_family": self.image_family,
+                "image_labels": self.image_labels,
                 "image_name": self.image_name,
                 "source_label": self.source_label,
+                "zone": self.zone,
+                "machine_type": self.config.machine_type,
+                "disk_size": self.config.disk_size,
+                "disk_type": self.config.disk_type,
+                "subnetwork": self.config.subnetwork,
+                "tags": self.config.tags,
+                "preemptible": self.config.preemptible,
+                "spot": self.config.spot,
+                "use_iap": self.config.use_iap,
             }),
         );
         match result {
@@ -65,3 +125,102 @@ impl PackerSource for GcloudPackerSource {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::api::DEBIAN_11;
+
+    use super::*;
+
+    fn source(config: GcloudConfig) -> GcloudPackerSource {
+        GcloudPackerSource {
+            config,
+            image_description: "eighty4 archetype image for debian:11 baked by swoon".to_string(),
+            image_family: "eighty4-archetype-debian-11".to_string(),
+            image_labels: vec!(("swoon-org".to_string(), "eighty4".to_string())),
+            image_name: "eighty4-archetype-debian-11-1".to_string(),
+            project_id: "eighty4".to_string(),
+            source_image: "debian-11-bullseye-v20211209".to_string(),
+            source_label: "archetype-debian-11".to_string(),
+            source_method: SourceImageMethod::Family("debian-11".to_string()),
+            zone: "us-west1-b".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_to_hcl_with_default_config() {
+        let hcl = source(GcloudConfig::default()).to_hcl();
+        assert!(hcl.contains("image_family = \"eighty4-archetype-debian-11\""));
+        assert!(hcl.contains("image_description = \"eighty4 archetype image for debian:11 baked by swoon\""));
+        assert!(hcl.contains("image_labels = {\n        \"swoon-org\" = \"eighty4\"\n    }\n"));
+        assert!(hcl.contains("zone         = \"us-west1-b\""));
+        assert!(hcl.contains("machine_type = \"e2-medium\"\n}"));
+        assert!(!hcl.contains("disk_size"));
+        assert!(!hcl.contains("tags"));
+        assert!(!hcl.contains("spot"));
+    }
+
+    #[test]
+    fn test_to_hcl_with_config() {
+        let hcl = source(GcloudConfig {
+            disk_size: Some(20),
+            disk_type: Some("pd-ssd".to_string()),
+            subnetwork: Some("packer".to_string()),
+            tags: vec!("packer".to_string(), "ssh".to_string()),
+            preemptible: true,
+            use_iap: true,
+            ..GcloudConfig::default()
+        }).to_hcl();
+        assert!(hcl.contains("disk_size    = 20\n"));
+        assert!(hcl.contains("disk_type    = \"pd-ssd\"\n"));
+        assert!(hcl.contains("subnetwork   = \"packer\"\n"));
+        assert!(hcl.contains("tags         = [\"packer\", \"ssh\"]\n"));
+        assert!(hcl.contains("preemptible  = true\n"));
+        assert!(hcl.contains("use_iap      = true\n}"));
+    }
+
+    #[test]
+    fn test_to_hcl_with_spot() {
+        let hcl = source(GcloudConfig {
+            spot: true,
+            ..GcloudConfig::default()
+        }).to_hcl();
+        assert!(hcl.contains("spot         = true\n"));
+        assert!(!hcl.contains("preemptible"));
+    }
+
+    #[test]
+    fn test_image_labels() {
+        assert_eq!(GcloudPackerSource::image_labels("Eighty4.io", "postgres"), vec!(
+            ("swoon-org".to_string(), "eighty4-io".to_string()),
+            ("swoon-image".to_string(), "postgres".to_string()),
+        ));
+    }
+
+    #[test]
+    fn test_to_hcl_with_provenance() {
+        let mut source = source(GcloudConfig::default());
+        source.set_provenance(&Provenance {
+            inputs_hash: "abc".to_string(),
+            git: None,
+            source_image: "debian-11-bullseye-v20211209".to_string(),
+            os: DEBIAN_11,
+        }).unwrap();
+        let hcl = source.to_hcl();
+        assert!(hcl.contains("\"swoon-inputs-hash\" = \"abc\"\n"));
+        assert!(hcl.contains("\"swoon-os\" = \"debian-11\"\n"));
+    }
+
+    #[test]
+    fn test_to_hcl_with_short_inputs_hash() {
+        let mut source = source(GcloudConfig::default());
+        let inputs_hash = "0123456789abcdef".repeat(4);
+        source.set_provenance(&Provenance {
+            inputs_hash: inputs_hash.clone(),
+            git: None,
+            source_image: "debian-11-bullseye-v20211209".to_string(),
+            os: DEBIAN_11,
+        }).unwrap();
+        assert!(source.to_hcl().contains(format!("\"swoon-inputs-hash\" = \"{}\"\n", &inputs_hash[..32]).as_str()));
+    }
+}
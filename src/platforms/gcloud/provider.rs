@@ -0,0 +1,105 @@
+use std::path::PathBuf;
+
+use futures::executor;
+
+use crate::api::{CloudPlatform, OperatingSystem, task};
+use crate::api::binaries::{BinaryPaths, PathLookup};
+use crate::api::config::{DeprecationState, EnvironmentConfig, SwoonConfig};
+use crate::images::archive::ArchivedImage;
+use crate::images::ImageSpec;
+use crate::packer::PackerSource;
+use crate::platforms::gcloud::cli::GcloudCli;
+use crate::platforms::gcloud::{GcloudContext, images};
+use crate::platforms::gcloud::packer::GcloudPackerSource;
+use crate::platforms::images::{latest_in_family, PlatformImage, promoted_labels};
+use crate::platforms::provider::PlatformProvider;
+
+pub struct GcloudProvider {
+    gcloud_ctx: GcloudContext,
+    gcloud_path: PathBuf,
+}
+
+impl GcloudProvider {
+    fn gcloud_cli(&self) -> GcloudCli {
+        GcloudCli::new(self.gcloud_path.clone())
+    }
+
+    fn gcloud_images_by_prefix(&self, prefix: &str) -> task::Result<Vec<PlatformImage>> {
+        executor::block_on(self.gcloud_cli().images_by_prefix(&self.gcloud_ctx.default_project_id, prefix))
+    }
+}
+
+impl PlatformProvider for GcloudProvider {
+    fn init(binary_paths: &BinaryPaths, cfg: &SwoonConfig, _: &CloudPlatform) -> task::Result<Self> {
+        let gcloud_path = binary_paths.gcloud_path()?;
+        Ok(Self {
+            gcloud_ctx: executor::block_on(GcloudContext::init(gcloud_path.clone(), cfg))?,
+            gcloud_path,
+        })
+    }
+
+    fn supports_instance_images() -> bool {
+        true
+    }
+
+    fn platform(&self) -> CloudPlatform {
+        CloudPlatform::GCP
+    }
+
+    fn validate_image_name(&self, name: &str) -> task::Result<()> {
+        images::validate_image_name(name)
+    }
+
+    fn has_image_families(&self) -> bool {
+        true
+    }
+
+    fn supports_deprecation(&self) -> bool {
+        true
+    }
+
+    fn current_os_image(&self, os: &OperatingSystem) -> task::Result<Option<String>> {
+        Ok(Some(executor::block_on(self.gcloud_cli().image_name_by_os(os))?))
+    }
+
+    fn packer_source(&self, cfg: &SwoonConfig, spec: &ImageSpec) -> task::Result<Box<dyn PackerSource>> {
+        Ok(GcloudPackerSource::from_spec(cfg, &self.gcloud_ctx, self.gcloud_cli(), spec)?)
+    }
+
+    fn images_by_prefix(&self, prefix: &str) -> task::Result<Option<Vec<PlatformImage>>> {
+        Ok(Some(self.gcloud_images_by_prefix(prefix)?))
+    }
+
+    fn latest_image(&self, prefix: &str) -> task::Result<Option<String>> {
+        Ok(latest_in_family(prefix, self.gcloud_images_by_prefix(prefix)?).map(|image| image.name))
+    }
+
+    fn delete_image(&self, image: &PlatformImage) -> task::Result<()> {
+        executor::block_on(self.gcloud_cli().delete_image(&self.gcloud_ctx.default_project_id, &image.name))
+    }
+
+    fn deprecate_image(&self, image: &PlatformImage, state: &DeprecationState, replacement: &str) -> task::Result<()> {
+        executor::block_on(self.gcloud_cli().deprecate_image(
+            &self.gcloud_ctx.default_project_id, &image.name, state.to_str(), replacement))
+    }
+
+    fn promote_image(&self,
+                     source: &ArchivedImage,
+                     image_name: &str,
+                     environment: &EnvironmentConfig) -> task::Result<String> {
+        let gcloud_cli = self.gcloud_cli();
+        let project_id = match &environment.project {
+            Some(project_id) => project_id.clone(),
+            None => executor::block_on(gcloud_cli.default_project_id())?,
+        };
+        let source_labels = executor::block_on(gcloud_cli.image_labels(&self.gcloud_ctx.default_project_id, &source.image_name))?;
+        executor::block_on(gcloud_cli.copy_image(
+            &self.gcloud_ctx.default_project_id,
+            &source.image_name,
+            &project_id,
+            image_name,
+            &promoted_labels(source_labels, &source.image_name),
+        ))?;
+        Ok(image_name.to_string())
+    }
+}
@@ -0,0 +1,178 @@
+use time::OffsetDateTime;
+
+use crate::api::{CloudPlatform, OperatingSystem, task};
+use crate::api::config::{DeprecationState, EnvironmentConfig};
+use crate::images::archive::ArchivedImage;
+use crate::images::lifecycle::in_family;
+use crate::api::util::parse_timestamp;
+use crate::SwoonContext;
+
+/// Label added to promoted images with the name of the image they were copied from.
+pub const PROMOTED_FROM_LABEL: &str = "swoon-promoted-from";
+
+/// Labels of a promoted image: the provenance labels of the image it was copied from, with the
+/// promoted from label replaced by the name of the copied image.
+pub fn promoted_labels(source_labels: Vec<(String, String)>, source_image_name: &str) -> Vec<(String, String)> {
+    let mut labels: Vec<(String, String)> = source_labels.into_iter()
+        .filter(|(name, _)| name != PROMOTED_FROM_LABEL)
+        .collect();
+    labels.push((PROMOTED_FROM_LABEL.to_string(), source_image_name.to_string()));
+    labels
+}
+
+/// An image owned by the org's account or project on a cloud platform.
+pub struct PlatformImage {
+    pub name: String,
+    pub id: String,
+    pub created_at: OffsetDateTime,
+    pub status: String,
+    /// The image's inputs hash label, shortened on platforms with short label values.
+    pub inputs_hash: Option<String>,
+}
+
+impl PlatformImage {
+    /// Parses tab separated name, id, creation timestamp and status fields from cli output.
+    /// An optional fifth field with a deprecation state takes precedence over the status, and an
+    /// optional sixth field holds the inputs hash label.
+    pub fn parse_lines(output: &str) -> task::Result<Vec<Self>> {
+        output.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                let fields: Vec<&str> = line.split('\t').map(|field| field.trim()).collect();
+                if fields.len() < 4 {
+                    return task::Error::result(format!("unexpected image listing {}", line));
+                }
+                Ok(Self {
+                    name: fields[0].to_string(),
+                    id: fields[1].to_string(),
+                    created_at: parse_timestamp(fields[2])?,
+                    status: fields.get(4).filter(|state| !state.is_empty())
+                        .unwrap_or(&fields[3])
+                        .to_lowercase(),
+                    inputs_hash: fields.get(5)
+                        .filter(|inputs_hash| !inputs_hash.is_empty() && **inputs_hash != "None")
+                        .map(|inputs_hash| inputs_hash.to_string()),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Lists the images with names starting with the given prefix,
+/// or None for platforms that swoon can not yet query for images.
+pub fn images_by_prefix(ctx: &SwoonContext, platform: &CloudPlatform, prefix: &str) -> task::Result<Option<Vec<PlatformImage>>> {
+    ctx.platforms.provider(platform)?.images_by_prefix(prefix)
+}
+
+pub fn delete_image(ctx: &SwoonContext, platform: &CloudPlatform, image: &PlatformImage) -> task::Result<()> {
+    ctx.platforms.provider(platform)?.delete_image(image)
+}
+
+/// Marks an image replaced by a newer image of its family.
+pub fn deprecate_image(ctx: &SwoonContext,
+                       platform: &CloudPlatform,
+                       image: &PlatformImage,
+                       state: &DeprecationState,
+                       replacement: &str) -> task::Result<()> {
+    ctx.platforms.provider(platform)?.deprecate_image(image, state, replacement)
+}
+
+/// Resolves the current upstream image for an OS, or None for platforms with OS images
+/// that swoon can not resolve to an exact image.
+pub fn current_os_image(ctx: &SwoonContext, platform: &CloudPlatform, os: &OperatingSystem) -> task::Result<Option<String>> {
+    ctx.platforms.provider(platform)?.current_os_image(os)
+}
+
+/// Copies an image into the project or region of another environment and returns the id of the copy.
+pub fn promote_image(ctx: &SwoonContext,
+                     source: &ArchivedImage,
+                     image_name: &str,
+                     environment: &EnvironmentConfig) -> task::Result<String> {
+    ctx.platforms.provider(&source.platform)?.promote_image(source, image_name, environment)
+}
+
+/// The most recently created image of the family named by an image name prefix, leaving out the
+/// images of other OS variants or environments with names that start with the same prefix.
+pub fn latest_in_family(prefix: &str, images: Vec<PlatformImage>) -> Option<PlatformImage> {
+    images.into_iter()
+        .filter(|image| in_family(prefix, &image.name))
+        .max_by_key(|image| image.created_at)
+}
+
+/// Checks an image name against the naming rules of the platform it will be created on.
+pub fn validate_image_name(ctx: &SwoonContext, platform: &CloudPlatform, name: &str) -> task::Result<()> {
+    ctx.platforms.provider(platform)?.validate_image_name(name)
+}
+
+/// Extracts the image ID from a regional artifact ID that packer records as `{region}:{image_id}`.
+pub fn regional_image_id(artifact_id: &str) -> String {
+    artifact_id.rsplit(':').next().unwrap_or(artifact_id).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn test_platform_image_parse_lines() {
+        let images = PlatformImage::parse_lines(
+            "eighty4-archetype-debian-11-1\t123\t2022-01-04T07:31:02.123-08:00\tREADY\n").unwrap();
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].name, "eighty4-archetype-debian-11-1");
+        assert_eq!(images[0].id, "123");
+        assert_eq!(images[0].created_at.unix_timestamp(), 1641310262);
+        assert_eq!(images[0].status, "ready");
+        assert_eq!(images[0].inputs_hash, None);
+        let labeled = PlatformImage::parse_lines(
+            "eighty4-archetype-debian-11-1\tami-123\t2022-01-04T07:31:02Z\tavailable\t\tabc").unwrap();
+        assert_eq!(labeled[0].status, "available");
+        assert_eq!(labeled[0].inputs_hash, Some("abc".to_string()));
+        let unlabeled = PlatformImage::parse_lines(
+            "eighty4-archetype-debian-11-1\tami-123\t2022-01-04T07:31:02Z\tavailable\t\tNone").unwrap();
+        assert_eq!(unlabeled[0].inputs_hash, None);
+        let deprecated = PlatformImage::parse_lines(
+            "eighty4-archetype-debian-11-1\t123\t2022-01-04T07:31:02Z\tREADY\tDEPRECATED").unwrap();
+        assert_eq!(deprecated[0].status, "deprecated");
+        assert!(PlatformImage::parse_lines("").unwrap().is_empty());
+        assert!(PlatformImage::parse_lines("eighty4-archetype-debian-11-1\t123").is_err());
+    }
+
+    #[test]
+    fn test_regional_image_id() {
+        assert_eq!(regional_image_id("us-east-1:ami-0123456789"), "ami-0123456789");
+        assert_eq!(regional_image_id("nyc3:104567689"), "104567689");
+    }
+
+    #[test]
+    fn test_promoted_labels() {
+        let source_labels = vec!(
+            ("swoon-inputs-hash".to_string(), "abc".to_string()),
+            (PROMOTED_FROM_LABEL.to_string(), "eighty4-archetype-debian-11-1".to_string()),
+        );
+        assert_eq!(promoted_labels(source_labels, "eighty4-archetype-debian-11-dev-1"), vec!(
+            ("swoon-inputs-hash".to_string(), "abc".to_string()),
+            (PROMOTED_FROM_LABEL.to_string(), "eighty4-archetype-debian-11-dev-1".to_string()),
+        ));
+    }
+
+    #[test]
+    fn test_latest_in_family() {
+        let image = |name: &str, created_at| PlatformImage {
+            name: name.to_string(),
+            id: format!("ami-{}", name.len()),
+            created_at,
+            status: "available".to_string(),
+            inputs_hash: None,
+        };
+        let latest = latest_in_family("eighty4-archetype-ubuntu-2004-", vec!(
+            image("eighty4-archetype-ubuntu-2004-1", datetime!(2022-01-01 00:00 UTC)),
+            image("eighty4-archetype-ubuntu-2004-2", datetime!(2022-01-02 00:00 UTC)),
+            image("eighty4-archetype-ubuntu-2004-minimal-3", datetime!(2022-01-03 00:00 UTC)),
+            image("eighty4-archetype-ubuntu-2004-stg-4", datetime!(2022-01-04 00:00 UTC)),
+        ));
+        assert_eq!(latest.map(|image| image.name), Some("eighty4-archetype-ubuntu-2004-2".to_string()));
+        assert!(latest_in_family("eighty4-archetype-debian-11-", Vec::new()).is_none());
+    }
+}
@@ -0,0 +1,36 @@
+use crate::api::{OperatingSystem, task};
+
+pub fn image_id_by_os(os: &OperatingSystem) -> task::Result<String> {
+    match os {
+        OperatingSystem::Debian { version } => Ok(format!("linode/debian{}", version)),
+        OperatingSystem::Ubuntu { minimal: true, .. } => task::Error::result(format!(
+            "{} is not available as a Linode image", os)),
+        OperatingSystem::Ubuntu { version, .. } => Ok(format!(
+            "linode/ubuntu{}.{:02}", version.major, version.minor)),
+    }
+}
+
+/// Image labels are at most 50 letters, digits, hyphens, underscores and periods.
+pub fn validate_image_name(name: &str) -> task::Result<()> {
+    let valid = !name.is_empty() && name.len() <= 50
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c));
+    if valid {
+        task::SUCCESS
+    } else {
+        task::Error::result(format!("{} is not a valid Linode image label", name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::{DEBIAN_11, UBUNTU_2004, UBUNTU_2004_MINIMAL};
+
+    use super::*;
+
+    #[test]
+    fn test_image_id_by_os() {
+        assert_eq!(image_id_by_os(&DEBIAN_11), Ok("linode/debian11".to_string()));
+        assert_eq!(image_id_by_os(&UBUNTU_2004), Ok("linode/ubuntu20.04".to_string()));
+        assert!(image_id_by_os(&UBUNTU_2004_MINIMAL).is_err());
+    }
+}
@@ -0,0 +1,60 @@
+use std::env;
+
+use crate::api::task;
+use crate::api::config::SwoonConfig;
+use crate::api::yaml::{ConfigErrors, YamlNode};
+
+pub mod images;
+pub mod packer;
+pub mod provider;
+
+const ERR_API_TOKEN: &str = r"packer requires a Linode api token to create images
+
+run 'export LINODE_TOKEN=$my_api_token'";
+
+const TOKEN_ENV_VAR: &str = "LINODE_TOKEN";
+
+#[derive(Clone)]
+pub struct LinodeConfig {
+    pub plan: String,
+    pub region: String,
+}
+
+impl LinodeConfig {
+    const DEFAULT_PLAN: &'static str = "g6-nanode-1";
+    const DEFAULT_REGION: &'static str = "us-east";
+
+    pub fn default() -> Self {
+        Self {
+            plan: Self::DEFAULT_PLAN.to_string(),
+            region: Self::DEFAULT_REGION.to_string(),
+        }
+    }
+
+    pub fn parse(yaml: &YamlNode, errors: &mut ConfigErrors) -> Self {
+        yaml.mapping(&["plan", "region"], errors);
+        Self {
+            plan: yaml.get("plan").string(errors).unwrap_or_else(|| Self::DEFAULT_PLAN.to_string()),
+            region: yaml.get("region").string(errors).unwrap_or_else(|| Self::DEFAULT_REGION.to_string()),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct LinodeContext {
+    pub plan: String,
+    pub region: String,
+}
+
+impl LinodeContext {
+    pub fn init(cfg: &SwoonConfig) -> task::Result<Self> {
+        if env::var(TOKEN_ENV_VAR).map_or(true, |t| t.is_empty()) {
+            return task::Error::result(ERR_API_TOKEN);
+        }
+        Ok(Self {
+            plan: cfg.platforms.linode.plan.clone(),
+            region: cfg.environment.as_ref().and_then(|e| e.region.clone())
+                .unwrap_or_else(|| cfg.platforms.linode.region.clone()),
+        })
+    }
+}
@@ -0,0 +1,76 @@
+use crate::api::config::SwoonConfig;
+use crate::api::task;
+use crate::api::output::template::{Template, template_object};
+use crate::images::{ImageSource, ImageSpec};
+use crate::images::provenance::Provenance;
+use crate::packer::{PackerPlugin, PackerSource, source};
+use crate::platforms::linode::{images, LinodeContext};
+
+pub struct LinodePackerSource {
+    image_id: String,
+    image_name: String,
+    plan: String,
+    provenance: Option<String>,
+    region: String,
+    source_label: String,
+}
+
+impl LinodePackerSource {
+    pub fn from_spec(cfg: &SwoonConfig,
+                     linode_ctx: &LinodeContext,
+                     spec: &ImageSpec) -> task::Result<Box<Self>> {
+        let os = match &spec.source {
+            ImageSource::OperatingSystem { os } => os,
+            ImageSource::Image { .. } | ImageSource::Family { .. } => return source::unsupported_image_source(spec),
+        };
+        Ok(Box::from(Self {
+            image_id: images::image_id_by_os(os)?,
+            image_name: spec.image_name(cfg),
+            plan: linode_ctx.plan.clone(),
+            provenance: None,
+            region: linode_ctx.region.clone(),
+            source_label: spec.source_label(),
+        }))
+    }
+}
+
+impl PackerSource for LinodePackerSource {
+    fn name(&self) -> String {
+        format!("source.linode.{}", self.source_label)
+    }
+
+    fn plugin(&self) -> PackerPlugin {
+        PackerPlugin {
+            name: "linode".into(),
+            source: "github.com/linode/linode".into(),
+            version: ">= 1.0.0".into(),
+        }
+    }
+
+    fn source_image(&self) -> String {
+        self.image_id.clone()
+    }
+
+    fn set_provenance(&mut self, provenance: &Provenance) -> task::Result<()> {
+        self.provenance = Some(provenance.description());
+        task::SUCCESS
+    }
+
+    fn to_hcl(&self) -> String {
+        let result = Template::render(
+            include_bytes!("source.pkr.hcl.liquid"),
+            &template_object!({
+                "image_id": self.image_id,
+                "image_name": self.image_name,
+                "plan": self.plan,
+                "provenance": self.provenance,
+                "region": self.region,
+                "source_label": self.source_label,
+            }),
+        );
+        match result {
+            Ok(s) => s,
+            Err(e) => e.exit(),
+        }
+    }
+}
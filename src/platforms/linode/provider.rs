@@ -0,0 +1,30 @@
+use crate::api::{CloudPlatform, task};
+use crate::api::binaries::BinaryPaths;
+use crate::api::config::SwoonConfig;
+use crate::images::ImageSpec;
+use crate::packer::PackerSource;
+use crate::platforms::linode::{images, LinodeContext};
+use crate::platforms::linode::packer::LinodePackerSource;
+use crate::platforms::provider::PlatformProvider;
+
+/// Swoon can not yet query Linode for images, so the api token packer needs is only checked
+/// when rendering a packer source.
+pub struct LinodeProvider {}
+
+impl PlatformProvider for LinodeProvider {
+    fn init(_: &BinaryPaths, _: &SwoonConfig, _: &CloudPlatform) -> task::Result<Self> {
+        Ok(Self {})
+    }
+
+    fn platform(&self) -> CloudPlatform {
+        CloudPlatform::Linode
+    }
+
+    fn validate_image_name(&self, name: &str) -> task::Result<()> {
+        images::validate_image_name(name)
+    }
+
+    fn packer_source(&self, cfg: &SwoonConfig, spec: &ImageSpec) -> task::Result<Box<dyn PackerSource>> {
+        Ok(LinodePackerSource::from_spec(cfg, &LinodeContext::init(cfg)?, spec)?)
+    }
+}
@@ -1,42 +1,106 @@
-use futures::executor;
+use std::cell::RefCell;
+use std::rc::Rc;
 
-use gcloud::GcloudContext;
+use digitalocean::DigitalOceanConfig;
+use gcloud::GcloudConfig;
+use linode::LinodeConfig;
+use vultr::VultrConfig;
 
-use crate::api::binaries::{BinaryPaths, PathLookup};
+use crate::api::binaries::BinaryPaths;
+use crate::api::{CloudPlatform, task};
 use crate::api::config::SwoonConfig;
+use crate::api::yaml::{ConfigErrors, YamlNode};
+use crate::platforms::provider::PlatformProvider;
 
+pub mod aws;
+pub mod azure;
+pub mod digitalocean;
+pub mod external;
 pub mod gcloud;
+pub mod images;
+pub mod linode;
 pub mod packer;
+pub mod provider;
+pub mod vultr;
 
+/// Platform specific settings from the platforms section of swoon.yml.
 #[derive(Clone)]
-pub struct PlatformContexts {
-    pub gcloud: Option<GcloudContext>,
+pub struct PlatformConfigs {
+    pub digitalocean: DigitalOceanConfig,
+    pub gcp: GcloudConfig,
+    pub linode: LinodeConfig,
+    pub vultr: VultrConfig,
+    /// Settings of external platforms, passed to their plugin as json.
+    pub external: Vec<(String, serde_json::Value)>,
 }
 
-impl PlatformContexts {
+impl PlatformConfigs {
+    const BUILT_IN_KEYS: [&'static str; 4] = ["digitalocean", "gcp", "linode", "vultr"];
+
     pub fn default() -> Self {
         Self {
-            gcloud: None,
+            digitalocean: DigitalOceanConfig::default(),
+            gcp: GcloudConfig::default(),
+            linode: LinodeConfig::default(),
+            vultr: VultrConfig::default(),
+            external: Vec::new(),
         }
     }
 
+    pub fn parse(yaml: &YamlNode, errors: &mut ConfigErrors) -> Self {
+        let external_keys: Vec<String> = yaml.keys().into_iter()
+            .filter(|key| !Self::BUILT_IN_KEYS.contains(&key.as_str()))
+            .filter(|key| matches!(external::parse_configured_platform(key), Ok(CloudPlatform::External(_))))
+            .collect();
+        let mut keys = Self::BUILT_IN_KEYS.to_vec();
+        keys.extend(external_keys.iter().map(|key| key.as_str()));
+        yaml.mapping(&keys, errors);
+        Self {
+            digitalocean: DigitalOceanConfig::parse(&yaml.get("digitalocean"), errors),
+            gcp: GcloudConfig::parse(&yaml.get("gcp"), errors),
+            linode: LinodeConfig::parse(&yaml.get("linode"), errors),
+            vultr: VultrConfig::parse(&yaml.get("vultr"), errors),
+            external: external_keys.iter()
+                .map(|key| (key.clone(), yaml.get(key).to_json()))
+                .collect(),
+        }
+    }
+
+    pub fn external_config(&self, name: &str) -> serde_json::Value {
+        self.external.iter()
+            .find(|(key, _)| key == name)
+            .map_or(serde_json::Value::Null, |(_, config)| config.clone())
+    }
+}
+
+/// Providers of the platforms a project bakes on, or has baked on before, each initialized the
+/// first time a command uses the platform so that commands only pay for the platform cli calls they need.
+pub struct PlatformContexts {
+    binary_paths: BinaryPaths,
+    config: Option<SwoonConfig>,
+    providers: RefCell<Vec<Rc<dyn PlatformProvider>>>,
+}
+
+impl PlatformContexts {
     pub fn init(binary_paths: &BinaryPaths, config_opt: &Option<SwoonConfig>) -> Self {
-        let config = match config_opt {
-            None => return Self::default(),
-            Some(cfg) => cfg,
-        };
-        let gcloud_ctx_result = executor::block_on(GcloudContext::init(
-            binary_paths.gcloud_path(), &config));
-        let gcloud = match gcloud_ctx_result {
-            Ok(gcloud) => Some(gcloud),
-            Err(e) => e.exit(),
-        };
         Self {
-            gcloud,
+            binary_paths: binary_paths.clone(),
+            config: config_opt.clone(),
+            providers: RefCell::new(Vec::new()),
         }
     }
 
-    pub fn gcloud_ctx(&self) -> &GcloudContext {
-        self.gcloud.as_ref().expect("no gcloud ctx")
+    fn config(&self) -> &SwoonConfig {
+        self.config.as_ref().expect("no config")
+    }
+
+    /// The provider of a platform, initialized on first use.
+    pub fn provider(&self, platform: &CloudPlatform) -> task::Result<Rc<dyn PlatformProvider>> {
+        if let Some(provider) = self.providers.borrow().iter().find(|provider| &provider.platform() == platform) {
+            return Ok(provider.clone());
+        }
+        let provider: Rc<dyn PlatformProvider> = Rc::from(provider::init(&self.binary_paths, self.config(), platform)?);
+        self.providers.borrow_mut().push(provider.clone());
+        Ok(provider)
     }
 }
@@ -1,20 +1,19 @@
 pub mod source {
-    use crate::api::{CloudPlatform, OperatingSystem, task};
+    use crate::api::task;
+    use crate::images::ImageSpec;
     use crate::packer::PackerSource;
-    use crate::platforms::gcloud::packer::GcloudPackerSource;
     use crate::SwoonContext;
 
-    fn not_yet_implemented(platform: &CloudPlatform) -> ! {
-        task::fatal(format!(
-            "unable to create packer source for unimplemented platform {}",
-            platform.to_str())
-        )
+    pub fn from_spec(ctx: &SwoonContext, spec: &ImageSpec) -> task::Result<Box<dyn PackerSource>> {
+        let cfg = ctx.config();
+        let provider = ctx.platforms.provider(&spec.platform)?;
+        provider.validate_image_name(&spec.image_name(cfg))?;
+        provider.packer_source(cfg, spec)
     }
 
-    pub fn from_os(ctx: &SwoonContext, platform: &CloudPlatform, os: &OperatingSystem) -> Box<dyn PackerSource> {
-        match platform {
-            CloudPlatform::GCP => GcloudPackerSource::from_os(ctx.config(), ctx.platforms.gcloud_ctx(), os),
-            _ => not_yet_implemented(platform),
-        }
+    pub fn unsupported_image_source<T>(spec: &ImageSpec) -> task::Result<T> {
+        task::Error::result(format!(
+            "building {} from image {} is not supported on {}",
+            spec.label, spec.source_string(), spec.platform.to_str()))
     }
 }
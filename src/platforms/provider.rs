@@ -0,0 +1,126 @@
+use crate::api::{CloudPlatform, OperatingSystem, task};
+use crate::api::binaries::BinaryPaths;
+use crate::api::config::{DeprecationState, EnvironmentConfig, SwoonConfig};
+use crate::images::archive::ArchivedImage;
+use crate::images::ImageSpec;
+use crate::packer::PackerSource;
+use crate::platforms::aws::provider::AwsProvider;
+use crate::platforms::azure::provider::AzureProvider;
+use crate::platforms::digitalocean::provider::DigitalOceanProvider;
+use crate::platforms::external::provider::ExternalProvider;
+use crate::platforms::gcloud::provider::GcloudProvider;
+use crate::platforms::linode::provider::LinodeProvider;
+use crate::platforms::images::PlatformImage;
+use crate::platforms::vultr::provider::VultrProvider;
+
+/// Everything swoon does on a cloud platform, implemented once per platform and reached through
+/// the providers of PlatformContexts instead of matching on CloudPlatform throughout the codebase.
+pub trait PlatformProvider {
+    /// Resolves the platform's account, project or region for the config and checks that the
+    /// platform's cli is authed to it.
+    fn init(binary_paths: &BinaryPaths, cfg: &SwoonConfig, platform: &CloudPlatform) -> task::Result<Self> where Self: Sized;
+
+    /// Whether swoon can look up the latest archetype image on the platform to build instance
+    /// images from, checked when parsing swoon.yml before any provider is initialized.
+    fn supports_instance_images() -> bool where Self: Sized {
+        false
+    }
+
+    fn platform(&self) -> CloudPlatform;
+
+    /// Checks an image name against the naming rules of the platform.
+    fn validate_image_name(&self, _name: &str) -> task::Result<()> {
+        task::SUCCESS
+    }
+
+    /// Whether images belong to image families that packer can build from, so that instance images
+    /// are built from the archetype family instead of the latest archetype image.
+    fn has_image_families(&self) -> bool {
+        false
+    }
+
+    /// Whether older images of a family can be marked with a deprecation state.
+    fn supports_deprecation(&self) -> bool {
+        false
+    }
+
+    /// Extracts the image ID from the artifact ID packer records in its manifest.
+    fn image_id_from_artifact(&self, artifact_id: &str) -> String {
+        artifact_id.to_string()
+    }
+
+    /// Resolves the current upstream image for an OS, or None when the platform's OS images
+    /// can not be resolved to an exact image.
+    fn current_os_image(&self, _os: &OperatingSystem) -> task::Result<Option<String>> {
+        Ok(None)
+    }
+
+    fn packer_source(&self, cfg: &SwoonConfig, spec: &ImageSpec) -> task::Result<Box<dyn PackerSource>>;
+
+    /// Lists the org's images with names starting with the given prefix,
+    /// or None when swoon can not yet query the platform for images.
+    fn images_by_prefix(&self, _prefix: &str) -> task::Result<Option<Vec<PlatformImage>>> {
+        Ok(None)
+    }
+
+    /// Resolves the most recently created image of the family named by the given prefix.
+    fn latest_image(&self, _prefix: &str) -> task::Result<Option<String>> {
+        task::Error::result(format!("looking up images is not supported on {}", self.platform().to_str()))
+    }
+
+    fn delete_image(&self, _image: &PlatformImage) -> task::Result<()> {
+        task::Error::result(format!("deleting images is not supported on {}", self.platform().to_str()))
+    }
+
+    fn deprecate_image(&self, _image: &PlatformImage, _state: &DeprecationState, _replacement: &str) -> task::Result<()> {
+        task::Error::result(format!("deprecating images is not supported on {}", self.platform().to_str()))
+    }
+
+    /// Copies an image into the project or region of another environment and returns the id of the copy.
+    fn promote_image(&self,
+                     _source: &ArchivedImage,
+                     _image_name: &str,
+                     _environment: &EnvironmentConfig) -> task::Result<String> {
+        task::Error::result(format!("promoting images is not supported on {}", self.platform().to_str()))
+    }
+}
+
+type ProviderInit = fn(&BinaryPaths, &SwoonConfig, &CloudPlatform) -> task::Result<Box<dyn PlatformProvider>>;
+
+/// What swoon knows about a platform's provider before initializing it.
+struct ProviderRegistration {
+    init: ProviderInit,
+    supports_instance_images: bool,
+}
+
+impl ProviderRegistration {
+    fn of<P: PlatformProvider + 'static>() -> Self {
+        Self {
+            init: |binary_paths, cfg, platform| Ok(Box::new(P::init(binary_paths, cfg, platform)?)),
+            supports_instance_images: P::supports_instance_images(),
+        }
+    }
+}
+
+/// The provider of each platform, with external platforms provided by their `swoon-platform-<name>` plugin.
+fn registration(platform: &CloudPlatform) -> ProviderRegistration {
+    match platform {
+        CloudPlatform::AWS => ProviderRegistration::of::<AwsProvider>(),
+        CloudPlatform::Azure => ProviderRegistration::of::<AzureProvider>(),
+        CloudPlatform::DigitalOcean => ProviderRegistration::of::<DigitalOceanProvider>(),
+        CloudPlatform::GCP => ProviderRegistration::of::<GcloudProvider>(),
+        CloudPlatform::Linode => ProviderRegistration::of::<LinodeProvider>(),
+        CloudPlatform::Vultr => ProviderRegistration::of::<VultrProvider>(),
+        CloudPlatform::External(_) => ProviderRegistration::of::<ExternalProvider>(),
+    }
+}
+
+/// Initializes the provider of a platform.
+pub fn init(binary_paths: &BinaryPaths, cfg: &SwoonConfig, platform: &CloudPlatform) -> task::Result<Box<dyn PlatformProvider>> {
+    (registration(platform).init)(binary_paths, cfg, platform)
+}
+
+/// Whether instance images can be built on a platform.
+pub fn supports_instance_images(platform: &CloudPlatform) -> bool {
+    registration(platform).supports_instance_images
+}
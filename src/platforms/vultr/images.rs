@@ -0,0 +1,26 @@
+use crate::api::{OperatingSystem, task};
+
+pub fn os_id_by_os(os: &OperatingSystem) -> task::Result<u32> {
+    match os {
+        OperatingSystem::Debian { version: 11 } => Ok(477),
+        OperatingSystem::Debian { version: 10 } => Ok(352),
+        OperatingSystem::Debian { version: 9 } => Ok(244),
+        OperatingSystem::Ubuntu { version, minimal: false } if version.major == 20 => Ok(387),
+        OperatingSystem::Ubuntu { version, minimal: false } if version.major == 18 => Ok(270),
+        _ => task::Error::result(format!("{} is not available as a Vultr image", os)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::{DEBIAN_11, UBUNTU_2004, UBUNTU_2004_MINIMAL};
+
+    use super::*;
+
+    #[test]
+    fn test_os_id_by_os() {
+        assert_eq!(os_id_by_os(&DEBIAN_11), Ok(477));
+        assert_eq!(os_id_by_os(&UBUNTU_2004), Ok(387));
+        assert!(os_id_by_os(&UBUNTU_2004_MINIMAL).is_err());
+    }
+}
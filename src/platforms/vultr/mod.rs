@@ -0,0 +1,60 @@
+use std::env;
+
+use crate::api::task;
+use crate::api::config::SwoonConfig;
+use crate::api::yaml::{ConfigErrors, YamlNode};
+
+pub mod images;
+pub mod packer;
+pub mod provider;
+
+const ERR_API_KEY: &str = r"packer requires a Vultr api key to create snapshots
+
+run 'export VULTR_API_KEY=$my_api_key'";
+
+const API_KEY_ENV_VAR: &str = "VULTR_API_KEY";
+
+#[derive(Clone)]
+pub struct VultrConfig {
+    pub plan: String,
+    pub region: String,
+}
+
+impl VultrConfig {
+    const DEFAULT_PLAN: &'static str = "vc2-1c-1gb";
+    const DEFAULT_REGION: &'static str = "ewr";
+
+    pub fn default() -> Self {
+        Self {
+            plan: Self::DEFAULT_PLAN.to_string(),
+            region: Self::DEFAULT_REGION.to_string(),
+        }
+    }
+
+    pub fn parse(yaml: &YamlNode, errors: &mut ConfigErrors) -> Self {
+        yaml.mapping(&["plan", "region"], errors);
+        Self {
+            plan: yaml.get("plan").string(errors).unwrap_or_else(|| Self::DEFAULT_PLAN.to_string()),
+            region: yaml.get("region").string(errors).unwrap_or_else(|| Self::DEFAULT_REGION.to_string()),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct VultrContext {
+    pub plan: String,
+    pub region: String,
+}
+
+impl VultrContext {
+    pub fn init(cfg: &SwoonConfig) -> task::Result<Self> {
+        if env::var(API_KEY_ENV_VAR).map_or(true, |k| k.is_empty()) {
+            return task::Error::result(ERR_API_KEY);
+        }
+        Ok(Self {
+            plan: cfg.platforms.vultr.plan.clone(),
+            region: cfg.environment.as_ref().and_then(|e| e.region.clone())
+                .unwrap_or_else(|| cfg.platforms.vultr.region.clone()),
+        })
+    }
+}
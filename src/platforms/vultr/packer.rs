@@ -0,0 +1,76 @@
+use crate::api::config::SwoonConfig;
+use crate::api::task;
+use crate::api::output::template::{Template, template_object};
+use crate::images::{ImageSource, ImageSpec};
+use crate::images::provenance::Provenance;
+use crate::packer::{PackerPlugin, PackerSource, source};
+use crate::platforms::vultr::{images, VultrContext};
+
+pub struct VultrPackerSource {
+    image_name: String,
+    os_id: u32,
+    plan: String,
+    provenance: Option<String>,
+    region: String,
+    source_label: String,
+}
+
+impl VultrPackerSource {
+    pub fn from_spec(cfg: &SwoonConfig,
+                     vultr_ctx: &VultrContext,
+                     spec: &ImageSpec) -> task::Result<Box<Self>> {
+        let os = match &spec.source {
+            ImageSource::OperatingSystem { os } => os,
+            ImageSource::Image { .. } | ImageSource::Family { .. } => return source::unsupported_image_source(spec),
+        };
+        Ok(Box::from(Self {
+            image_name: spec.image_name(cfg),
+            os_id: images::os_id_by_os(os)?,
+            plan: vultr_ctx.plan.clone(),
+            provenance: None,
+            region: vultr_ctx.region.clone(),
+            source_label: spec.source_label(),
+        }))
+    }
+}
+
+impl PackerSource for VultrPackerSource {
+    fn name(&self) -> String {
+        format!("source.vultr.{}", self.source_label)
+    }
+
+    fn plugin(&self) -> PackerPlugin {
+        PackerPlugin {
+            name: "vultr".into(),
+            source: "github.com/vultr/vultr".into(),
+            version: ">= 2.0.0".into(),
+        }
+    }
+
+    fn source_image(&self) -> String {
+        self.os_id.to_string()
+    }
+
+    fn set_provenance(&mut self, provenance: &Provenance) -> task::Result<()> {
+        self.provenance = Some(provenance.description());
+        task::SUCCESS
+    }
+
+    fn to_hcl(&self) -> String {
+        let result = Template::render(
+            include_bytes!("source.pkr.hcl.liquid"),
+            &template_object!({
+                "image_name": self.image_name,
+                "os_id": self.os_id,
+                "plan": self.plan,
+                "provenance": self.provenance,
+                "region": self.region,
+                "source_label": self.source_label,
+            }),
+        );
+        match result {
+            Ok(s) => s,
+            Err(e) => e.exit(),
+        }
+    }
+}
@@ -0,0 +1,26 @@
+use crate::api::{CloudPlatform, task};
+use crate::api::binaries::BinaryPaths;
+use crate::api::config::SwoonConfig;
+use crate::images::ImageSpec;
+use crate::packer::PackerSource;
+use crate::platforms::provider::PlatformProvider;
+use crate::platforms::vultr::VultrContext;
+use crate::platforms::vultr::packer::VultrPackerSource;
+
+/// Swoon can not yet query Vultr for snapshots, so the api token packer needs is only checked
+/// when rendering a packer source.
+pub struct VultrProvider {}
+
+impl PlatformProvider for VultrProvider {
+    fn init(_: &BinaryPaths, _: &SwoonConfig, _: &CloudPlatform) -> task::Result<Self> {
+        Ok(Self {})
+    }
+
+    fn platform(&self) -> CloudPlatform {
+        CloudPlatform::Vultr
+    }
+
+    fn packer_source(&self, cfg: &SwoonConfig, spec: &ImageSpec) -> task::Result<Box<dyn PackerSource>> {
+        Ok(VultrPackerSource::from_spec(cfg, &VultrContext::init(cfg)?, spec)?)
+    }
+}
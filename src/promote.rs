@@ -0,0 +1,162 @@
+use dialoguer::Input;
+use time::OffsetDateTime;
+
+use crate::api::{command, task};
+use crate::api::command::Name::Init;
+use crate::api::config::SwoonConfig;
+use crate::api::context::SwoonContext;
+use crate::api::output::file::Directory::ImageArchive;
+use crate::images::archive::{archive_promoted_image, ArchivedImage, read_archived_images};
+use crate::images::image_name_prefix;
+use crate::platforms::images as platform_images;
+
+pub struct PromoteOpts<'a> {
+    pub approve: bool,
+    pub image_name: &'a str,
+    pub to: &'a str,
+}
+
+/// Copies an archived image into another environment without rebaking it.
+pub fn promote_image(ctx: &SwoonContext, opts: &PromoteOpts) -> command::Result {
+    if !ctx.has_config() {
+        return command::Error::with_command_suggestions(
+            "There's no swoon.yml file in your current directory",
+            vec!(Init),
+        );
+    }
+    let source_environment = ctx.environment().map(|environment| environment.name.as_str());
+    if source_environment == Some(opts.to) {
+        return command::Error::result(format!("{} is already in the {} environment", opts.image_name, opts.to).as_str());
+    }
+    let target_cfg = ctx.config().clone().with_environment(opts.to)?;
+    let archived_images = read_archived_images()?;
+    let source = match archived_images.iter().find(|archived| archived.image_name == opts.image_name
+        && archived.environment.as_deref() == source_environment) {
+        Some(archived) => archived,
+        None => return command::Error::result(match source_environment {
+            None => format!("{} is not in the image archive", opts.image_name),
+            Some(environment) => format!("{} is not in the image archive for the {} environment", opts.image_name, environment),
+        }.as_str()),
+    };
+    let image_name = promoted_image_name(ctx.config(), &target_cfg, source)?;
+    if archived_images.iter().any(|archived| archived.image_name == image_name
+        && archived.platform == source.platform
+        && archived.environment.as_deref() == Some(opts.to)) {
+        return command::Error::result(format!("{} has already been promoted to {} as {}",
+                                              source.image_name, opts.to, image_name).as_str());
+    }
+    platform_images::validate_image_name(ctx, &source.platform, &image_name)?;
+
+    if !opts.approve && !prompt_for_approval(ctx, source, &image_name, opts.to) {
+        ctx.write_line("Cancelling image promotion");
+        return command::SUCCESS;
+    }
+
+    let started_at = OffsetDateTime::now_utc();
+    let target_environment = target_cfg.environment.as_ref().expect("no target environment");
+    let image_id = platform_images::promote_image(ctx, source, &image_name, target_environment)?;
+    let promoted = ArchivedImage {
+        image_name,
+        image_id,
+        environment: Some(opts.to.to_string()),
+        promoted_from: Some(source.image_name.clone()),
+        started_at,
+        finished_at: OffsetDateTime::now_utc(),
+        ..source.clone()
+    };
+    archive_promoted_image(&promoted, source)?;
+    ctx.write_line(format!("Promoted {} to {} as {}", source.image_name, opts.to, promoted.image_name));
+    ctx.write_line(format!("Archived {} to {}",
+                           promoted.image_name,
+                           ImageArchive.path().join(promoted.archive_path()).display()));
+    command::SUCCESS
+}
+
+/// Names the promoted image with the name prefix of the target environment and the version of its source.
+fn promoted_image_name(cfg: &SwoonConfig, target_cfg: &SwoonConfig, source: &ArchivedImage) -> task::Result<String> {
+    let source_prefix = image_name_prefix(cfg, &source.label, &source.os);
+    match source.image_name.strip_prefix(&source_prefix) {
+        Some(version) => Ok(format!("{}{}", image_name_prefix(target_cfg, &source.label, &source.os), version)),
+        None => task::Error::result(format!("{} is not named with the image name prefix {}",
+                                            source.image_name, source_prefix)),
+    }
+}
+
+fn prompt_for_approval(ctx: &SwoonContext, source: &ArchivedImage, image_name: &str, to: &str) -> bool {
+    ctx.write_line(format!("Promoting {} on {} to the {} environment as {}",
+                           source.image_name, source.platform.to_str(), to, image_name));
+    let result = Input::<String>::new()
+        .with_prompt("Type yes to promote image")
+        .allow_empty(true)
+        .interact_text();
+    match result {
+        Ok(approval) => approval == "yes",
+        Err(e) => task::Error::from(e).exit(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use crate::api::{CloudPlatform, DEBIAN_11};
+    use crate::api::config::{ArchetypeConfig, BinariesConfig, EnvironmentConfig, ImageNamingConfig, ImageRetentionConfig};
+    use crate::platforms::PlatformConfigs;
+
+    use super::*;
+
+    fn config(environment: &str, name_suffix: Option<&str>) -> SwoonConfig {
+        SwoonConfig {
+            org_name: "eighty4".to_string(),
+            default_os: DEBIAN_11,
+            default_platform: CloudPlatform::GCP,
+            archetype: ArchetypeConfig::from_defaults(&CloudPlatform::GCP, &DEBIAN_11),
+            environments: Vec::new(),
+            environment: Some(EnvironmentConfig {
+                name: environment.to_string(),
+                project: None,
+                account: None,
+                region: None,
+                zone: None,
+                name_suffix: name_suffix.map(|s| s.to_string()),
+            }),
+            image_naming: ImageNamingConfig::default(),
+            image_retention: ImageRetentionConfig::default(),
+            pin_source_images: false,
+            instances: Vec::new(),
+            platforms: PlatformConfigs::default(),
+            binaries: BinariesConfig::default(),
+        }
+    }
+
+    fn archived_image(image_name: &str) -> ArchivedImage {
+        ArchivedImage {
+            image_name: image_name.to_string(),
+            image_id: image_name.to_string(),
+            inputs_hash: None,
+            label: "archetype".to_string(),
+            platform: CloudPlatform::GCP,
+            os: DEBIAN_11,
+            environment: Some("dev".to_string()),
+            source_image: "debian-11-bullseye-v20211209".to_string(),
+            promoted_from: None,
+            started_at: datetime!(2022-01-04 15:30:12 UTC),
+            finished_at: datetime!(2022-01-04 15:42:30 UTC),
+        }
+    }
+
+    #[test]
+    fn test_promoted_image_name() {
+        let source = archived_image("eighty4-archetype-debian-11-dev-20220104153012");
+        let image_name = promoted_image_name(&config("dev", Some("dev")), &config("prod", None), &source).unwrap();
+        assert_eq!(image_name, "eighty4-archetype-debian-11-20220104153012");
+        let image_name = promoted_image_name(&config("dev", Some("dev")), &config("prod", Some("prod")), &source).unwrap();
+        assert_eq!(image_name, "eighty4-archetype-debian-11-prod-20220104153012");
+    }
+
+    #[test]
+    fn test_promoted_image_name_requires_source_prefix() {
+        let source = archived_image("eighty4-archetype-debian-11-20220104153012");
+        assert!(promoted_image_name(&config("dev", Some("dev")), &config("prod", None), &source).is_err());
+    }
+}
@@ -58,7 +58,7 @@ fn swoon_init_unhappy_when_no_org_name() -> io::Result<()> {
         .assert()
         .failure();
 
-    assert_eq!(false, work_dir.path().join("swoon.yml").exists());
+    assert!(!work_dir.path().join("swoon.yml").exists());
 
     Ok(())
 }